@@ -0,0 +1,105 @@
+use keyevents::{Key, KeyEvent};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {   // things the player can do while falling
+    Left,
+    Right,
+    Up,
+    Down,
+    Pause,
+    Quit,
+    Restart,
+}
+
+const ACTIONS: [(&str, Action); 7] = [
+    ("left", Action::Left),
+    ("right", Action::Right),
+    ("up", Action::Up),
+    ("down", Action::Down),
+    ("pause", Action::Pause),
+    ("quit", Action::Quit),
+    ("restart", Action::Restart),
+];
+
+// (name, movement keys for left, right, up & down, and the quit keys)
+const PRESETS: [(&str, [&str; 4], &str); 3] = [
+    ("arrows", ["left", "right", "up", "down"], "esc"),
+    ("wasd", ["a", "d", "w", "s"], "esc"),
+    ("hjkl", ["h", "l", "k", "j"], "esc q"),     // for the vim folks
+];
+
+impl Action {
+    pub fn names() -> Vec<&'static str> {
+        ACTIONS.iter().map(|&(name, _)| name).collect()
+    }
+
+    pub fn named(name: &str) -> Option<Action> {
+        ACTIONS.iter().find(|&&(action, _)| action == name).map(|&(_, action)| action)
+    }
+
+    // the key that the game (and the replays) know this action by
+    pub fn key(&self) -> Option<Key> {
+        match *self {
+            Action::Left => Some(Key::Left),
+            Action::Right => Some(Key::Right),
+            Action::Up => Some(Key::Up),
+            Action::Down => Some(Key::Down),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bindings {   // which key does what
+    keys: Vec<(Key, Action)>,
+}
+
+impl Default for Bindings {
+    fn default() -> Bindings {
+        Bindings::preset("arrows").unwrap_or(Bindings { keys: vec![] })
+    }
+}
+
+impl Bindings {
+    pub fn presets() -> Vec<&'static str> {
+        PRESETS.iter().map(|&(name, _, _)| name).collect()
+    }
+
+    pub fn preset(name: &str) -> Option<Bindings> {
+        let &(_, moves, quit) = PRESETS.iter().find(|&&(preset, _, _)| preset == name)?;
+        let mut bindings = Bindings { keys: vec![] };
+        let actions = [Action::Left, Action::Right, Action::Up, Action::Down];
+        for (key, &action) in moves.iter().zip(&actions) {
+            bindings.keys.push((Key::from_name(key)?, action));
+        }
+
+        bindings.keys.push((Key::Char('p'), Action::Pause));
+        for key in quit.split_whitespace() {
+            bindings.keys.push((Key::from_name(key)?, Action::Quit));
+        }
+
+        bindings.keys.push((Key::Char('r'), Action::Restart));
+        Some(bindings)
+    }
+
+    // replaces the keys bound to the action (a key can only do one thing, so it's unbound from everything else)
+    pub fn bind(&mut self, action: Action, keys: &[Key]) {
+        self.keys.retain(|&(key, bound)| bound != action && !keys.contains(&key));
+        self.keys.extend(keys.iter().map(|&key| (key, action)));
+    }
+
+    pub fn keys(&self, action: Action) -> Vec<Key> {
+        self.keys.iter().filter(|&&(_, bound)| bound == action).map(|&(key, _)| key).collect()
+    }
+
+    // what the keypress means (Ctrl-C always quits, no matter what)
+    pub fn action(&self, event: KeyEvent) -> Option<Action> {
+        if event.mods.ctrl && event.key == Key::Char('c') {
+            return Some(Action::Quit)
+        } else if event.mods.ctrl || event.mods.alt {
+            return None
+        }
+
+        self.keys.iter().find(|&&(key, _)| key == event.key).map(|&(_, action)| action)
+    }
+}
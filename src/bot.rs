@@ -0,0 +1,96 @@
+use config::Controls;
+use input::InputSource;
+use keyevents::Key;
+use reach::Reach;
+use sprites::{Cliff, Game};
+
+// A simple-minded player which looks at the cliffs coming up (and the ones it's passing by) and heads for
+// the nearest column where the jumper won't run into any of them. It presses one key per tick at most,
+// which is about as fast as a person holding a key (and with momentum, it brakes when it's going too fast).
+#[derive(Clone, Debug, Default)]
+pub struct Bot {
+    last_tick: Option<usize>,   // tick at which the last move was made
+}
+
+impl Bot {
+    pub fn new() -> Bot {
+        Bot::default()
+    }
+
+    // the closest column (where the jumper can actually be) that's clear of the cliffs for a while,
+    // and which can be reached without running into anything on the way
+    fn target(game: &Game) -> Option<usize> {
+        let jumper = game.jumper();
+        let ((x, y), (width, height)) = (jumper.position(), jumper.size());
+        let area_width = game.area().width.0;
+        let step = match game.config().controls {   // (how far it gets in a tick, about)
+            Controls::Steps => jumper.step().0,
+            Controls::Momentum => 1,    // (it takes a while to pick up speed, and then to slow down)
+        };
+
+        let lookahead = area_width / (2 * step);    // ticks that it takes to get across half the box
+        // whether the jumper can be at the column from the given tick to the other (counting from now)
+        let clear = |col: usize, from: usize, to: usize| !game.cliffs().iter().any(|cliff| {
+            let ((cliff_x, cliff_y), (cliff_width, cliff_height)) = (cliff.position(), cliff.size());
+            let rise = game.config().cliff_y as isize;
+            let (top, bottom) = (cliff_y as isize - to as isize * rise,
+                                 (cliff_y + cliff_height) as isize - from as isize * rise);
+            top < (y + height) as isize && bottom > y as isize && cliff_x < col + width && col < cliff_x + cliff_width
+        });
+
+        if clear(x, 0, lookahead) {
+            return Some(x)
+        }
+
+        // one step per tick, and the jumper can't touch the walls
+        let mut best = None;
+        for &right in &[false, true] {
+            for moves in 1.. {
+                let col = match right {
+                    true => x + moves * step,
+                    false if x > moves * step => x - moves * step,
+                    false => break,
+                };
+
+                if col + width >= area_width || !clear(col, moves - 1, moves) {
+                    break
+                } else if clear(col, moves - 1, moves - 1 + lookahead) {
+                    best = match best {
+                        Some((old, _)) if old <= moves => best,
+                        _ => Some((moves, col)),
+                    };
+                    break
+                }
+            }
+        }
+
+        best.map(|(_, col)| col)
+    }
+}
+
+impl InputSource for Bot {
+    fn next_move(&mut self, game: &Game, _now: u64) -> Option<Key> {
+        if self.last_tick == Some(game.ticks()) {
+            return None
+        }
+
+        self.last_tick = Some(game.ticks());
+        let jumper = game.jumper();
+        // (it stays where it is when there's nowhere to go, which takes some braking with momentum)
+        let col = Bot::target(game).unwrap_or(jumper.position().0);
+        let heading = jumper.heading(col as isize);
+        // the target doesn't know about the momentum (or the cliffs further down), so the key's double-checked
+        // for a way past the cliffs (and when there isn't, it tries not pressing anything, or the other keys)
+        let reach = Reach::new(jumper, game.config(), 0).every_tick();
+        let cliffs: Vec<&Cliff> = game.cliffs().iter().collect();
+        let push = |key: Option<Key>| match key {
+            Some(Key::Left) => -1,
+            Some(Key::Right) => 1,
+            _ => 0,
+        };
+
+        [heading, None, Some(Key::Left), Some(Key::Right)].iter().cloned()
+            .find(|&key| reach.clone().starting_with(push(key)).clears(&cliffs))
+            .unwrap_or(heading)
+    }
+}
@@ -0,0 +1,658 @@
+use frame::Theme;
+use bindings::{Action, Bindings};
+use keyevents::Key;
+use libc::c_uint;
+use std::{env, fs, io};
+use std::path::{Path, PathBuf};
+
+// (name, min, max) of everything that can be tweaked in a game
+const LIMITS: [(&str, usize, usize); 15] = [
+    ("width", 40, 500),         // the minimum is what's required for a smoother gameplay
+    ("height", 30, 500),
+    ("timeout", 10, 1000),      // in milliseconds
+    ("separation", 1, 100),
+    ("jumper_x", 1, 10),
+    ("jumper_y", 1, 10),
+    // the cliffs can skip past the jumper in a tick at these speeds (or with a diving jumper), which is only
+    // fine because the hits are checked for every row that they rise past it (and not just where they end up)
+    ("cliff_y", 1, 10),
+    ("repeat", 10, 500),        // in milliseconds
+    ("acceleration", 0, 90),    // in percent
+    ("dive", 0, 5),             // times faster than the cliffs (0 keeps the jumper where it is)
+    // for the momentum controls (speeds are in hundredths of a column for every tick)
+    ("thrust", 10, 500),
+    ("drag", 0, 90),            // in percent
+    ("max_speed", 50, 500),     // any faster, and the jumper could pass through the narrowest cliffs
+    ("lives", 1, 9),
+    ("tightness", 10, 100),     // in percent
+];
+
+const CONTROLS: [(&str, Controls); 2] = [
+    ("steps", Controls::Steps),
+    ("momentum", Controls::Momentum),
+];
+
+const OBSTACLES: [(&str, Obstacles); 6] = [
+    ("mixed", Obstacles::Mixed),
+    ("cliffs", Obstacles::Cliffs),
+    ("walls", Obstacles::Walls),
+    ("zigzag", Obstacles::ZigZag),
+    ("ledges", Obstacles::Ledges),
+    ("boulders", Obstacles::Boulders),
+];
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Controls {     // how the jumper moves sideways
+    #[default]
+    Steps,      // a fixed number of columns for every keypress
+    Momentum,   // keypresses push the jumper, which keeps going on its own (until the air slows it down)
+}
+
+impl Controls {
+    pub fn names() -> Vec<&'static str> {
+        CONTROLS.iter().map(|&(name, _)| name).collect()
+    }
+
+    pub fn named(name: &str) -> Option<Controls> {
+        CONTROLS.iter().find(|&&(controls, _)| controls == name).map(|&(_, controls)| controls)
+    }
+
+    pub fn name(&self) -> &'static str {
+        CONTROLS.iter().find(|&&(_, controls)| controls == *self).map(|&(name, _)| name).unwrap_or("steps")
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Obstacles {    // what's thrown at the jumper
+    #[default]
+    Mixed,      // all of the below, with more of them showing up as the score goes up
+    Cliffs,     // random cliffs on either side
+    Walls,      // walls all the way across, with a gap somewhere
+    ZigZag,     // walls with gaps that wind left & right
+    Ledges,     // wide ledges from the left & right walls (one after the other)
+    Boulders,   // bunches of small rocks
+}
+
+impl Obstacles {
+    pub fn names() -> Vec<&'static str> {
+        OBSTACLES.iter().map(|&(name, _)| name).collect()
+    }
+
+    pub fn named(name: &str) -> Option<Obstacles> {
+        OBSTACLES.iter().find(|&&(obstacles, _)| obstacles == name).map(|&(_, obstacles)| obstacles)
+    }
+
+    pub fn name(&self) -> &'static str {
+        OBSTACLES.iter().find(|&&(_, obstacles)| obstacles == *self).map(|&(name, _)| name).unwrap_or("mixed")
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GameConfig {     // playfield & difficulty parameters of a game
+    pub width: usize,       // width & height for game
+    pub height: usize,
+    // initial difficulty setting (game speed & cliff rate)
+    pub timeout: c_uint,
+    pub cliff_separation: usize,
+    // shift the jumper or cliff by X chars (change this if you change the width & height)
+    pub jumper_x: usize,
+    pub jumper_y: usize,    // (the jumper doesn't move up & down by itself, so this only rounds up the height)
+    pub cliff_y: usize,
+    // time between moves while a key's held down (and how much faster that gets after every move)
+    pub repeat: c_uint,
+    pub acceleration: usize,
+    pub dive: usize,    // how fast the jumper can dive (up & down do nothing when this is zero)
+    pub controls: Controls,
+    pub obstacles: Obstacles,
+    pub thrust: usize,      // speed gained for every keypress
+    pub drag: usize,        // speed lost for every tick
+    pub max_speed: usize,
+    pub lives: usize,
+    pub tightness: usize,   // how often (in %) the jumper has to move to get past the cliffs
+    pub theme: Theme,
+}
+
+impl Default for GameConfig {
+    fn default() -> GameConfig {
+        GameConfig {
+            width: 50,
+            height: 30,
+            timeout: 80,
+            cliff_separation: 8,
+            jumper_x: 3,
+            jumper_y: 1,
+            cliff_y: 1,
+            repeat: 60,
+            acceleration: 0,
+            dive: 0,
+            controls: Controls::default(),
+            obstacles: Obstacles::default(),
+            thrust: 60,
+            drag: 15,
+            max_speed: 300,
+            lives: 1,
+            tightness: 50,
+            theme: Theme::default(),
+        }
+    }
+}
+
+impl GameConfig {
+    pub fn names() -> Vec<&'static str> {
+        LIMITS.iter().map(|&(name, _, _)| name).collect()
+    }
+
+    pub fn limits(name: &str) -> Option<(usize, usize)> {
+        LIMITS.iter().find(|&&(limit, _, _)| limit == name).map(|&(_, min, max)| (min, max))
+    }
+
+    pub fn get(&self, name: &str) -> Option<usize> {
+        match name {
+            "width" => Some(self.width),
+            "height" => Some(self.height),
+            "timeout" => Some(self.timeout as usize),
+            "separation" => Some(self.cliff_separation),
+            "jumper_x" => Some(self.jumper_x),
+            "jumper_y" => Some(self.jumper_y),
+            "cliff_y" => Some(self.cliff_y),
+            "repeat" => Some(self.repeat as usize),
+            "acceleration" => Some(self.acceleration),
+            "dive" => Some(self.dive),
+            "thrust" => Some(self.thrust),
+            "drag" => Some(self.drag),
+            "max_speed" => Some(self.max_speed),
+            "lives" => Some(self.lives),
+            "tightness" => Some(self.tightness),
+            _ => None,
+        }
+    }
+
+    fn check(name: &str, value: usize) -> Result<(), String> {
+        let (min, max) = GameConfig::limits(name).ok_or_else(|| format!("Unknown setting '{}'", name))?;
+        match value < min || value > max {
+            true => Err(format!("{} should be between {} and {} (got {})", name, min, max, value)),
+            false => Ok(()),
+        }
+    }
+
+    // sets the value (by its name), after making sure that it's within limits
+    pub fn set(&mut self, name: &str, value: usize) -> Result<(), String> {
+        GameConfig::check(name, value)?;
+        match name {
+            "width" => self.width = value,
+            "height" => self.height = value,
+            "timeout" => self.timeout = value as c_uint,
+            "separation" => self.cliff_separation = value,
+            "jumper_x" => self.jumper_x = value,
+            "jumper_y" => self.jumper_y = value,
+            "cliff_y" => self.cliff_y = value,
+            "repeat" => self.repeat = value as c_uint,
+            "acceleration" => self.acceleration = value,
+            "dive" => self.dive = value,
+            "thrust" => self.thrust = value,
+            "drag" => self.drag = value,
+            "max_speed" => self.max_speed = value,
+            "lives" => self.lives = value,
+            "tightness" => self.tightness = value,
+            _ => return Err(format!("Unknown setting '{}'", name)),     // (a setting with limits but no field)
+        }
+
+        Ok(())
+    }
+
+    // checks whether the values make sense together
+    pub fn validate(&self) -> Result<(), String> {
+        for name in GameConfig::names() {   // in case someone's messed with the fields directly
+            GameConfig::check(name, self.get(name).unwrap_or(0))?;
+        }
+
+        if self.jumper_x * 4 > self.width {
+            Err(format!("jumper_x ({}) should be at most a quarter of the width ({})", self.jumper_x, self.width))
+        } else if self.jumper_y * 4 > self.height || self.cliff_y * 4 > self.height {
+            Err(format!("jumper_y ({}) and cliff_y ({}) should be at most a quarter of the height ({})",
+                        self.jumper_y, self.cliff_y, self.height))
+        } else if self.cliff_separation * 2 > self.height {
+            Err(format!("separation ({}) should be at most half the height ({})", self.cliff_separation, self.height))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// Config files are a (small) subset of TOML, holding the default settings and named profiles...
+//
+//      theme = "ocean"             # applies to all profiles
+//
+//      [profile.wide]
+//      width = 80
+//      timeout = 60
+//
+//      [profile.wide.keys]         # key bindings (a preset and/or the keys for each action)
+//      preset = "wasd"
+//      pause = "p space"
+//
+// Only integers, strings and booleans are supported (one key/value pair for each line).
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Int(i64),
+    Str(String),
+    Bool(bool),
+}
+
+struct Entry {
+    table: Vec<String>,     // table headers like `[profile.wide]` become `["profile", "wide"]`
+    header: (usize, usize),     // position of the table header
+    key: String,
+    value: Value,
+    pos: (usize, usize),    // (line, column) of the value (for reporting errors)
+}
+
+type ParseError = ((usize, usize), String);
+
+struct LineParser {
+    chars: Vec<char>,
+    idx: usize,     // current column (starting from zero)
+    line: usize,
+}
+
+impl LineParser {
+    fn new(text: &str, line: usize) -> LineParser {
+        LineParser {
+            chars: text.chars().collect(),
+            idx: 0,
+            line,
+        }
+    }
+
+    fn error<T>(&self, msg: &str) -> Result<T, ParseError> {
+        Err(((self.line, self.idx + 1), msg.to_owned()))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.idx).cloned()
+    }
+
+    fn skip_spaces(&mut self) {
+        while let Some(' ') | Some('\t') = self.peek() {
+            self.idx += 1;
+        }
+    }
+
+    // nothing but spaces and comments should be left in the line
+    fn finish(&mut self) -> Result<(), ParseError> {
+        self.skip_spaces();
+        match self.peek() {
+            None | Some('#') => Ok(()),
+            Some(_) => self.error("unexpected characters at the end of the line"),
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), ParseError> {
+        self.skip_spaces();
+        match self.peek() {
+            Some(found) if found == c => {
+                self.idx += 1;
+                Ok(())
+            },
+            _ => self.error(&format!("expected '{}'", c)),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        let quote = self.peek().unwrap_or('"');
+        self.idx += 1;
+        let mut string = String::new();
+        loop {
+            match (self.peek(), quote) {
+                (None, _) => return self.error("unterminated string"),
+                (Some(c), _) if c == quote => {
+                    self.idx += 1;
+                    return Ok(string)
+                },
+                (Some('\\'), '"') => {      // escapes only work in basic strings (not in 'literal' ones)
+                    self.idx += 1;
+                    string.push(match self.peek() {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        _ => return self.error("unknown escape sequence"),
+                    });
+                },
+                (Some(c), _) => string.push(c),
+            }
+
+            self.idx += 1;
+        }
+    }
+
+    fn key(&mut self) -> Result<String, ParseError> {
+        self.skip_spaces();
+        match self.peek() {
+            Some('"') | Some('\'') => self.string(),
+            _ => {
+                let start = self.idx;
+                while let Some(c) = self.peek() {
+                    match c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                        true => self.idx += 1,
+                        false => break,
+                    }
+                }
+
+                match self.idx > start {
+                    true => Ok(self.chars[start..self.idx].iter().collect()),
+                    false => self.error("expected a key"),
+                }
+            },
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, ParseError> {
+        self.skip_spaces();
+        match self.peek() {
+            Some('"') | Some('\'') => self.string().map(Value::Str),
+            Some(_) => {
+                let start = self.idx;
+                while let Some(c) = self.peek() {
+                    match c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '+' {
+                        true => self.idx += 1,
+                        false => break,
+                    }
+                }
+
+                let word = self.chars[start..self.idx].iter().filter(|&&c| c != '_').collect::<String>();
+                match word.as_str() {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    _ => match word.parse() {
+                        Ok(num) => Ok(Value::Int(num)),
+                        Err(_) => {
+                            self.idx = start;
+                            self.error("expected a string, integer or boolean")
+                        },
+                    },
+                }
+            },
+            None => self.error("expected a value"),
+        }
+    }
+}
+
+fn parse(text: &str) -> Result<Vec<Entry>, ParseError> {
+    let mut entries = vec![];
+    let (mut table, mut header) = (vec![], (0, 0));
+    for (num, line) in text.lines().enumerate() {
+        let mut parser = LineParser::new(line, num + 1);
+        parser.skip_spaces();
+        match parser.peek() {
+            None | Some('#') => continue,
+            Some('[') => {
+                header = (parser.line, parser.idx + 1);
+                parser.idx += 1;
+                table = vec![parser.key()?];
+                loop {
+                    parser.skip_spaces();
+                    match parser.peek() {
+                        Some('.') => {
+                            parser.idx += 1;
+                            table.push(parser.key()?);
+                        },
+                        _ => break,
+                    }
+                }
+
+                parser.expect(']')?;
+            },
+            Some(_) => {
+                let key = parser.key()?;
+                parser.expect('=')?;
+                parser.skip_spaces();
+                let pos = (parser.line, parser.idx + 1);
+                let value = parser.value()?;
+                entries.push(Entry { table: table.clone(), header, key, value, pos });
+            },
+        }
+
+        parser.finish()?;
+    }
+
+    Ok(entries)
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Profile {    // settings (and key bindings) loaded from the config file
+    pub config: GameConfig,
+    pub bindings: Bindings,
+    pub mouse: bool,    // whether the jumper follows the mouse pointer
+}
+
+impl Profile {
+    pub fn default_path() -> Option<PathBuf> {
+        let dir = match env::var_os("XDG_CONFIG_HOME") {
+            Some(ref dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        };
+
+        Some(dir.join("free-fall").join("config.toml"))
+    }
+
+    // loads from the default path (built-in defaults are used if there's no config file)
+    pub fn load(name: Option<&str>) -> Result<Profile, String> {
+        let path = match Profile::default_path() {
+            Some(path) => path,
+            None => return Profile::from_text("", "<none>", name),
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(text) => Profile::from_text(&text, &path.display().to_string(), name),
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Profile::from_text("", "<none>", name),
+            Err(err) => Err(format!("Can't read config file {}: {}", path.display(), err)),
+        }
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P, name: Option<&str>) -> Result<Profile, String> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+                      .map_err(|err| format!("Can't read config file {}: {}", path.display(), err))?;
+        Profile::from_text(&text, &path.display().to_string(), name)
+    }
+
+    // root settings apply to all profiles, and the named profile's settings override them
+    pub fn from_text(text: &str, path: &str, name: Option<&str>) -> Result<Profile, String> {
+        let error = |(line, col): (usize, usize), msg: String| format!("{}:{}:{}: {}", path, line, col, msg);
+        let entries = parse(text).map_err(|(pos, msg)| error(pos, msg))?;
+
+        let mut names = vec![];
+        for entry in &entries {
+            match entry.table.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+                [] | ["keys"] | ["profile", _] | ["profile", _, "keys"] => (),
+                _ => return Err(error(entry.header, format!("unknown table '[{}]'", entry.table.join(".")))),
+            }
+
+            if entry.table.len() > 1 && !names.contains(&entry.table[1]) {
+                names.push(entry.table[1].clone());
+            }
+        }
+
+        if let Some(name) = name {
+            if !names.iter().any(|n| n == name) {
+                return Err(match names.is_empty() {
+                    true => format!("Unknown profile '{}' (no profiles in {})", name, path),
+                    false => format!("Unknown profile '{}' (available: {})", name, names.join(", ")),
+                })
+            }
+        }
+
+        let mut profile = Profile::default();
+        // root entries go first, so that they get overridden by the profile
+        let selected = entries.iter().filter(|e| e.table.len() < 2)
+                              .chain(entries.iter().filter(|e| e.table.len() > 1 && Some(e.table[1].as_str()) == name));
+        for entry in selected {
+            let is_keys = entry.table.len() % 2 == 1;     // `[keys]` or `[profile.NAME.keys]`
+            profile.apply(entry, is_keys).map_err(|msg| error(entry.pos, msg))?;
+        }
+
+        profile.config.validate().map_err(|err| format!("{}: {}", path, err))?;
+        Ok(profile)
+    }
+
+    fn apply(&mut self, entry: &Entry, is_keys: bool) -> Result<(), String> {
+        match (&entry.value, is_keys) {
+            (Value::Str(preset), true) if entry.key == "preset" => {
+                self.bindings = Bindings::preset(preset).ok_or_else(|| {
+                    format!("unknown preset '{}' (available: {})", preset, Bindings::presets().join(", "))
+                })?;
+            },
+            (Value::Str(names), true) => {      // space-separated key names (like "a left")
+                let action = Action::named(&entry.key).ok_or_else(|| {
+                    format!("unknown action '{}' (available: {})", entry.key, Action::names().join(", "))
+                })?;
+                let keys = names.split_whitespace()
+                                .map(|name| Key::from_name(name).ok_or_else(|| format!("unknown key '{}'", name)))
+                                .collect::<Result<Vec<_>, _>>()?;
+                self.bindings.bind(action, &keys);
+            },
+            (_, true) => return Err(format!("expected key names for '{}'", entry.key)),
+            (Value::Str(theme), false) if entry.key == "theme" => {
+                self.config.theme = Theme::named(theme).ok_or_else(|| {
+                    format!("unknown theme '{}' (available: {})", theme, Theme::names().join(", "))
+                })?;
+            },
+            (Value::Str(controls), false) if entry.key == "controls" => {
+                self.config.controls = Controls::named(controls).ok_or_else(|| {
+                    format!("unknown controls '{}' (available: {})", controls, Controls::names().join(", "))
+                })?;
+            },
+            (Value::Str(obstacles), false) if entry.key == "obstacles" => {
+                self.config.obstacles = Obstacles::named(obstacles).ok_or_else(|| {
+                    format!("unknown obstacles '{}' (available: {})", obstacles, Obstacles::names().join(", "))
+                })?;
+            },
+            (&Value::Bool(mouse), false) if entry.key == "mouse" => self.mouse = mouse,
+            (&Value::Int(num), false) if GameConfig::limits(&entry.key).is_some() => match num < 0 {
+                true => return Err(format!("{} can't be negative", entry.key)),
+                false => self.config.set(&entry.key, num as usize)?,
+            },
+            _ => match ["theme", "controls", "obstacles", "mouse"].contains(&entry.key.as_str()) || GameConfig::limits(&entry.key).is_some() {
+                true => return Err(format!("invalid value for '{}'", entry.key)),
+                false => return Err(format!("unknown setting '{}'", entry.key)),
+            },
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Controls, GameConfig, Profile, Value};
+    use bindings::{Action, Bindings};
+    use frame::Theme;
+    use keyevents::Key;
+
+    fn error(text: &str) -> ((usize, usize), String) {
+        match parse(text) {
+            Ok(_) => panic!("{:?} should've failed", text),
+            Err(err) => err,
+        }
+    }
+
+    #[test]
+    fn error_positions() {
+        assert_eq!(error("width = 40\nheight = oops").0, (2, 10));
+        assert_eq!(error("\n\n  width = 40 x"), ((3, 14), "unexpected characters at the end of the line".to_owned()));
+        assert_eq!(error("theme = \"ocean"), ((1, 15), "unterminated string".to_owned()));
+        assert_eq!(error("theme = \"a\\qb\""), ((1, 12), "unknown escape sequence".to_owned()));
+        assert_eq!(error("[profile.wide"), ((1, 14), "expected ']'".to_owned()));
+        assert_eq!(error("= 40"), ((1, 1), "expected a key".to_owned()));
+        assert_eq!(error("width 40").1, "expected '='");
+        assert_eq!(error("width =").1, "expected a value");
+
+        let err = Profile::from_text("# comment\nwidth = -40", "fall.toml", None).unwrap_err();
+        assert_eq!(err, "fall.toml:2:9: width can't be negative");
+    }
+
+    #[test]
+    fn values_and_strings() {
+        let text = "a = 'C:\\n\\t' # literal\nb = \"x\\n\\t\\\"\\\\\"\nc = 1_000\nd = true\n\"e f\" = 'g'";
+        let entries = parse(text).unwrap();
+        let values = entries.iter().map(|e| (e.key.as_str(), e.value.clone())).collect::<Vec<_>>();
+        assert_eq!(values, vec![
+            ("a", Value::Str("C:\\n\\t".to_owned())),   // (no escapes in literal strings)
+            ("b", Value::Str("x\n\t\"\\".to_owned())),
+            ("c", Value::Int(1000)),
+            ("d", Value::Bool(true)),
+            ("e f", Value::Str("g".to_owned())),
+        ]);
+        assert_eq!(entries[1].pos, (2, 5));
+    }
+
+    #[test]
+    fn profiles_override_the_root() {
+        let text = "width = 60\ntimeout = 50\ntheme = \"ocean\"\n\n\
+                    [profile.wide]\nwidth = 120\ncontrols = \"momentum\"\n\n\
+                    [profile.other]\nwidth = 70";
+        let root = Profile::from_text(text, "fall.toml", None).unwrap();
+        assert_eq!((root.config.width, root.config.timeout), (60, 50));
+        assert_eq!(root.config.controls, Controls::Steps);
+
+        let wide = Profile::from_text(text, "fall.toml", Some("wide")).unwrap();
+        assert_eq!((wide.config.width, wide.config.timeout), (120, 50));
+        assert_eq!(wide.config.controls, Controls::Momentum);
+        assert_eq!(Some(wide.config.theme), Theme::named("ocean"));
+
+        // (the root settings apply even when they come after the profile)
+        let late = Profile::from_text("[profile.wide]\nwidth = 120\n[keys]\npause = \"x\"", "fall.toml", Some("wide"));
+        assert_eq!(late.unwrap().bindings.keys(Action::Pause), vec![Key::Char('x')]);
+    }
+
+    #[test]
+    fn profile_keys() {
+        let text = "[profile.vim.keys]\npreset = \"hjkl\"\npause = \"p space\"\nquit = 'esc'";
+        let profile = Profile::from_text(text, "fall.toml", Some("vim")).unwrap();
+        assert_eq!(profile.bindings.keys(Action::Left), vec![Key::Char('h')]);
+        assert_eq!(profile.bindings.keys(Action::Pause), vec![Key::Char('p'), Key::Char(' ')]);
+        assert_eq!(profile.bindings.keys(Action::Quit), vec![Key::Esc]);
+
+        // (the keys only apply to their own profile)
+        assert_eq!(Profile::from_text(text, "fall.toml", None).unwrap().bindings, Bindings::default());
+
+        let err = Profile::from_text("[profile.vim.keys]\njump = \"x\"", "fall.toml", Some("vim")).unwrap_err();
+        assert!(err.starts_with("fall.toml:2:8: unknown action 'jump'"), "{}", err);
+        let err = Profile::from_text("[profile.vim.keys]\nleft = 5", "fall.toml", Some("vim")).unwrap_err();
+        assert_eq!(err, "fall.toml:2:8: expected key names for 'left'");
+    }
+
+    #[test]
+    fn unknown_profiles_and_tables() {
+        let text = "[profile.wide]\nwidth = 80\n[profile.tall]\nheight = 80";
+        let err = Profile::from_text(text, "fall.toml", Some("huge")).unwrap_err();
+        assert_eq!(err, "Unknown profile 'huge' (available: wide, tall)");
+        let err = Profile::from_text("width = 80", "fall.toml", Some("huge")).unwrap_err();
+        assert_eq!(err, "Unknown profile 'huge' (no profiles in fall.toml)");
+
+        let err = Profile::from_text("width = 80\n\n  [settings]\nheight = 80", "fall.toml", None).unwrap_err();
+        assert_eq!(err, "fall.toml:3:3: unknown table '[settings]'");
+        let err = Profile::from_text("[profile.wide.colors]\nfg = 1", "fall.toml", None).unwrap_err();
+        assert_eq!(err, "fall.toml:1:1: unknown table '[profile.wide.colors]'");
+        let err = Profile::from_text("colour = 'red'", "fall.toml", None).unwrap_err();
+        assert_eq!(err, "fall.toml:1:10: unknown setting 'colour'");
+    }
+
+    #[test]
+    fn every_setting_has_its_field() {
+        for name in GameConfig::names() {   // (each one lands in its own field, and nowhere else)
+            let (min, max) = GameConfig::limits(name).unwrap();
+            let mut config = GameConfig::default();
+            let value = if config.get(name) == Some(max) { min } else { max };
+            config.set(name, value).unwrap();
+            assert_eq!(config.get(name), Some(value), "{}", name);
+            for other in GameConfig::names().into_iter().filter(|&other| other != name) {
+                assert_eq!(config.get(other), GameConfig::default().get(other), "{} changed {}", name, other);
+            }
+        }
+
+        assert_eq!(GameConfig::default().set("colour", 1), Err("Unknown setting 'colour'".to_owned()));
+    }
+}
@@ -0,0 +1,186 @@
+// Everything that's drawn goes through a `Frame`, which is a grid of cells (rather than a bunch of strings),
+// so that we don't have to worry about multi-byte characters, colors, or finding out who's where.
+
+use std::slice::Chunks;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Color {
+    Default,
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+}
+
+impl Color {
+    fn offset(&self) -> u8 {    // SGR codes are offsets from 30 (foreground) or 40 (background)
+        match *self {
+            Color::Black => 0,
+            Color::Red => 1,
+            Color::Green => 2,
+            Color::Yellow => 3,
+            Color::Blue => 4,
+            Color::Magenta => 5,
+            Color::Cyan => 6,
+            Color::White => 7,
+            Color::Default => 9,
+        }
+    }
+}
+
+// (name, jumper, cliffs) for the themes we've got
+const THEMES: [(&str, Color, Color); 5] = [
+    ("classic", Color::Yellow, Color::Default),
+    ("mono", Color::Default, Color::Default),
+    ("ocean", Color::Cyan, Color::Blue),
+    ("lava", Color::White, Color::Red),
+    ("forest", Color::Yellow, Color::Green),
+];
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Theme {      // colors of the game objects
+    pub jumper: Color,
+    pub cliff: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme { jumper: Color::Yellow, cliff: Color::Default }
+    }
+}
+
+impl Theme {
+    pub fn names() -> Vec<&'static str> {
+        THEMES.iter().map(|&(name, _, _)| name).collect()
+    }
+
+    pub fn named(name: &str) -> Option<Theme> {
+        THEMES.iter()
+              .find(|&&(theme, _, _)| theme == name)
+              .map(|&(_, jumper, cliff)| Theme { jumper, cliff })
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Attrs {
+    pub bold: bool,
+    pub dim: bool,
+    pub blink: bool,
+    pub reverse: bool,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Entity {   // things that can occupy a cell
+    Empty,
+    Jumper,
+    Cliff(usize),   // cliff's ID
+    PowerUp,
+    Coin,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Cell {
+    pub glyph: char,
+    pub fg: Color,
+    pub bg: Color,
+    pub attrs: Attrs,
+    pub owner: Entity,
+}
+
+impl Cell {
+    pub fn blank() -> Cell {
+        Cell::new(' ', Color::Default, Entity::Empty)
+    }
+
+    pub fn new(glyph: char, fg: Color, owner: Entity) -> Cell {
+        Cell {
+            glyph,
+            fg,
+            bg: Color::Default,
+            attrs: Attrs::default(),
+            owner,
+        }
+    }
+
+    // SGR escape sequence for drawing this cell's style
+    pub fn sgr(&self) -> String {
+        let mut codes = vec![0];
+        if self.attrs.bold { codes.push(1) }
+        if self.attrs.dim { codes.push(2) }
+        if self.attrs.blink { codes.push(5) }
+        if self.attrs.reverse { codes.push(7) }
+        codes.push(30 + self.fg.offset());
+        codes.push(40 + self.bg.offset());
+        let codes = codes.iter().map(|code| code.to_string()).collect::<Vec<_>>();
+        format!("\x1B[{}m", codes.join(";"))
+    }
+
+    pub fn same_style(&self, other: &Cell) -> bool {
+        (self.fg, self.bg, self.attrs) == (other.fg, other.bg, other.attrs)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,   // row-major
+}
+
+impl Frame {
+    pub fn new(width: usize, height: usize) -> Frame {
+        Frame {
+            width,
+            height,
+            cells: vec![Cell::blank(); width * height],
+        }
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&Cell> {
+        if x < self.width && y < self.height {
+            self.cells.get(y * self.width + x)
+        } else {
+            None
+        }
+    }
+
+    // returns the cell that was there before (cells outside the frame are simply ignored)
+    pub fn set(&mut self, x: usize, y: usize, cell: Cell) -> Option<Cell> {
+        if x < self.width && y < self.height {
+            let old = self.cells[y * self.width + x];
+            self.cells[y * self.width + x] = cell;
+            Some(old)
+        } else {
+            None
+        }
+    }
+
+    // writes a string from the given position using the cell's style (one cell for every `char`)
+    pub fn put_str(&mut self, x: usize, y: usize, string: &str, style: Cell) {
+        for (i, glyph) in string.chars().enumerate() {
+            self.set(x + i, y, Cell { glyph, ..style });
+        }
+    }
+
+    pub fn dim(&mut self) {     // for showing stuff on top of the frame
+        for cell in &mut self.cells {
+            cell.attrs.dim = true;
+        }
+    }
+
+    pub fn rows(&self) -> Chunks<'_, Cell> {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub fn lines(&self) -> Vec<String> {    // plain text (for those who don't care about colors)
+        self.rows().map(|row| row.iter().map(|cell| cell.glyph).collect()).collect()
+    }
+}
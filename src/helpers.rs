@@ -1,13 +1,18 @@
 use libc::{c_ushort, STDOUT_FILENO};
 use libc::ioctl;
 use libc::TIOCGWINSZ;
+use rand::{thread_rng, Rng};
 
-use std::iter::repeat;
-use {JUMPER_X, JUMPER_Y};
+use config::GameConfig;
 
 // minimum width & height (for a smoother gameplay)
 const WIN_COLS: usize = 40;
 const WIN_ROWS: usize = 30;
+// rows & columns taken by the box's borders, and the rows below it (an empty line, the HUD and the status line)
+pub const BORDERS: usize = 2;
+pub const LINES_BELOW: usize = 3;
+
+pub const MS: u64 = 1000000;    // nanoseconds in a millisecond
 
 #[repr(C)]
 struct WindowSize {
@@ -15,7 +20,7 @@ struct WindowSize {
     col: c_ushort,
 }
 
-fn window_size() -> Result<(usize, usize), &'static str> {      // get the current size of the terminal window
+pub fn window_size() -> Result<(usize, usize), &'static str> {      // get the current size of the terminal window
     let wsize = WindowSize { row: 0, col: 0 };
     let val = unsafe { ioctl(STDOUT_FILENO, TIOCGWINSZ, &wsize) };
     match val {
@@ -31,37 +36,69 @@ pub struct FallArea {   // necessary type which describes where the game objects
 }
 
 impl FallArea {
-    pub fn new(width: usize, height: usize) -> Result<FallArea, &'static str> {
-        let size_result = window_size();
-        if let Ok((rows, cols)) = size_result {
-            if (width < WIN_COLS) | (height < WIN_ROWS) | (rows < WIN_ROWS) | (cols < WIN_COLS) {
-                return Err("Minimum window size is 30 rows and 40 columns!")
-            } else if (cols - 2 < width) | (rows - 2 < height) {    // the extra "2" is for drawing the dashed box
-                return Err("Requested window size is less than what's available!")
-            } else {    // update the width and height so that it's a multiple of displacements (smoother gameplay)
-                let width = width + JUMPER_X - width % JUMPER_X;
-                let height = height + JUMPER_Y - height % JUMPER_Y;
-                return Ok(FallArea {
-                    width: (width, cols - width),
-                    height: (height, rows - height),
-                })
-            }
+    pub fn new(config: &GameConfig) -> Result<FallArea, &'static str> {
+        let (rows, cols) = window_size()?;
+        FallArea::fit(config, rows, cols)
+    }
+
+    // same as `new`, but for a window of known size (no terminal required, which is nice for headless games)
+    pub fn fit(config: &GameConfig, rows: usize, cols: usize) -> Result<FallArea, &'static str> {
+        let (width, height) = (config.width, config.height);
+        // the width and height are rounded up to a multiple of displacements (smoother gameplay)
+        let rounded = (round_up(width, config.jumper_x), round_up(height, config.jumper_y));
+        if (width < WIN_COLS) | (height < WIN_ROWS) | (rows < WIN_ROWS) | (cols < WIN_COLS) {
+            Err("Minimum window size is 30 rows and 40 columns!")
+        } else if FallArea::too_small(rounded, rows, cols) {
+            Err("Requested window size is less than what's available!")
+        } else {
+            Ok(FallArea {
+                width: (rounded.0, cols - rounded.0),
+                height: (rounded.1, rows - rounded.1),
+            })
+        }
+    }
+
+    // whether the box (of the given width & height) can't be drawn in the window, along with its borders
+    // and the lines below it
+    pub fn too_small((width, height): (usize, usize), rows: usize, cols: usize) -> bool {
+        cols < width + BORDERS || rows < height + BORDERS + LINES_BELOW
+    }
+
+    // same game area, but placed inside a window of different size
+    pub fn relayout(&self, rows: usize, cols: usize) -> Result<FallArea, &'static str> {
+        if FallArea::too_small((self.width.0, self.height.0), rows, cols) {
+            Err("Window is too small for the game!")
         } else {
-            Err(size_result.unwrap_err())
+            Ok(FallArea {
+                width: (self.width.0, cols - self.width.0),
+                height: (self.height.0, rows - self.height.0),
+            })
         }
     }
 }
 
+fn round_up(value: usize, step: usize) -> usize {
+    value + step - value % step
+}
+
 pub fn multiply(ch: &str, length: usize) -> String {    // I don't wanna write this every time! (DRY)
-    repeat(ch).take(length).collect()
+    ch.repeat(length)
 }
 
-pub fn print_msg(msg: &str, color: Option<&str>) {
+pub fn random_seed() -> u64 {      // for the games which don't care about replaying
+    thread_rng().gen()
+}
+
+pub fn colored(msg: &str, color: Option<&str>) -> String {
     let code = match color {    // these are enough for us!
         Some("B") => 96,
         Some("Y") => 93,
         Some("G") => 92,
         _ => 91,        // default to red
     };
-    println!("\r\n\t\x1B[{}m{}\x1B[0m\r\n", code, msg);
+    format!("\x1B[{}m{}\x1B[0m", code, msg)
+}
+
+pub fn print_msg(msg: &str, color: Option<&str>) {
+    println!("\r\n\t{}\r\n", colored(msg, color));
 }
@@ -0,0 +1,217 @@
+use bot::Bot;
+use config::GameConfig;
+use keyevents::{Key, KeyKind};
+use libc::c_uint;
+use mouse::Steering;
+use repeat::KeyRepeat;
+use replay::Replay;
+use sprites::Game;
+use std::path::Path;
+
+// Anything that can move the jumper. The game loop keeps the clock (and the terminal, for pausing and
+// quitting), and it asks the source for moves as it goes (after every keypress and before every tick),
+// so the same game can be played by a person, a recording, a script or a bot.
+pub trait InputSource {
+    // the next move (if there's one right now), which is asked for until there's nothing left
+    fn next_move(&mut self, game: &Game, now: u64) -> Option<Key>;
+
+    // milliseconds until the source makes a move on its own (sources that follow the ticks don't need this)
+    fn wait_ms(&self, _game: &Game, _now: u64) -> Option<c_uint> {
+        None
+    }
+
+    // movement keys pressed by the player (sources which don't care about the player simply ignore them)
+    fn press(&mut self, _key: Key, _kind: KeyKind, _now: u64) {}
+
+    // the mouse pointer is over some other column in the box (`None` lets go of the jumper)
+    fn point(&mut self, _column: Option<usize>) {}
+
+    // forget about whatever's going on (the game has been paused)
+    fn clear(&mut self) {}
+
+    // whether the source has run out of moves
+    fn finished(&self, _game: &Game) -> bool {
+        false
+    }
+
+    // seed of the game that the moves were meant for (if they were meant for one)
+    fn seed(&self) -> Option<u64> {
+        None
+    }
+
+    // settings of the game that the moves were meant for (the moves don't make sense in any other game)
+    fn config(&self) -> Option<GameConfig> {
+        None
+    }
+}
+
+// the player at the keyboard (and the mouse)
+#[derive(Clone, Debug)]
+pub struct Terminal {
+    pressed: Option<Key>,   // key that's just been pressed (and not fed yet)
+    held: KeyRepeat,
+    steering: Steering,
+}
+
+impl Terminal {
+    pub fn new(config: &GameConfig) -> Terminal {
+        Terminal {
+            pressed: None,
+            held: KeyRepeat::new(config),
+            steering: Steering::new(config),
+        }
+    }
+}
+
+impl InputSource for Terminal {
+    fn next_move(&mut self, game: &Game, now: u64) -> Option<Key> {
+        self.pressed.take()
+            .or_else(|| self.steering.steer(game.jumper(), now))
+            .or_else(|| self.held.repeat(now))
+    }
+
+    fn wait_ms(&self, game: &Game, now: u64) -> Option<c_uint> {
+        self.held.wait_ms(now).into_iter().chain(self.steering.wait_ms(game.jumper(), now)).min()
+    }
+
+    fn press(&mut self, key: Key, kind: KeyKind, now: u64) {
+        self.steering.point(None);      // keys take over from the mouse
+        if self.held.press(key, kind, now) {
+            self.pressed = Some(key);
+        }
+    }
+
+    fn point(&mut self, column: Option<usize>) {
+        self.steering.point(column);
+    }
+
+    fn clear(&mut self) {
+        self.pressed = None;
+        self.held.clear();
+    }
+}
+
+// A fixed sequence of moves, written as key names (left, right, up & down) and the number of ticks to
+// wait in between. For example, "left left 5 right" moves left twice, waits for 5 ticks and moves right.
+#[derive(Clone, Debug)]
+pub struct Script {
+    events: Vec<(usize, Key)>,      // (tick, key)
+    next: usize,    // index of the next event
+}
+
+impl Script {
+    pub fn new(events: Vec<(usize, Key)>) -> Script {
+        Script {
+            events,
+            next: 0,
+        }
+    }
+
+    pub fn parse(moves: &str) -> Result<Script, String> {
+        let (mut events, mut tick) = (vec![], 0);
+        for word in moves.split_whitespace() {
+            match (word.parse::<usize>(), Key::from_name(word)) {
+                (Ok(ticks), _) => tick += ticks,
+                (_, Some(key)) if [Key::Left, Key::Right, Key::Up, Key::Down].contains(&key) => {
+                    events.push((tick, key));
+                },
+                _ => return Err(format!("Invalid move '{}' in the script (expected left, right, up, down \
+                                         or a number of ticks)", word)),
+            }
+        }
+
+        Ok(Script::new(events))
+    }
+}
+
+impl InputSource for Script {
+    fn next_move(&mut self, game: &Game, _now: u64) -> Option<Key> {
+        match self.events.get(self.next) {
+            Some(&(tick, key)) if tick <= game.ticks() => {
+                self.next += 1;
+                Some(key)
+            },
+            _ => None,
+        }
+    }
+
+    fn finished(&self, _game: &Game) -> bool {
+        self.next == self.events.len()
+    }
+}
+
+// the moves from a recorded game (which ends when the recording does)
+#[derive(Clone, Debug)]
+pub struct ReplayInput {
+    script: Script,
+    ticks: usize,
+    seed: u64,
+    config: GameConfig,
+}
+
+impl ReplayInput {
+    pub fn new(replay: &Replay) -> ReplayInput {
+        ReplayInput {
+            script: Script::new(replay.events.clone()),
+            ticks: replay.ticks,
+            seed: replay.seed,
+            config: replay.config,
+        }
+    }
+}
+
+impl InputSource for ReplayInput {
+    fn next_move(&mut self, game: &Game, now: u64) -> Option<Key> {
+        self.script.next_move(game, now)
+    }
+
+    fn finished(&self, game: &Game) -> bool {
+        self.script.finished(game) && game.ticks() >= self.ticks
+    }
+
+    fn seed(&self) -> Option<u64> {
+        Some(self.seed)
+    }
+
+    fn config(&self) -> Option<GameConfig> {
+        Some(self.config)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Source {   // which input source plays the game (chosen at startup)
+    #[default]
+    Terminal,
+    Bot,
+    Replay(String),     // path to the replay file
+    Script(String),     // the moves
+}
+
+impl Source {
+    // sources are named like "terminal", "bot", "replay:FILE" or "script:MOVES"
+    pub fn parse(name: &str) -> Result<Source, String> {
+        let source = match name.find(':').map(|i| (&name[..i], &name[i + 1..])) {
+            None if name == "terminal" => Source::Terminal,
+            None if name == "bot" => Source::Bot,
+            Some(("replay", path)) if !path.is_empty() => Source::Replay(path.to_owned()),
+            Some(("script", moves)) => {
+                Script::parse(moves)?;      // so that mistakes are caught right away
+                Source::Script(moves.to_owned())
+            },
+            _ => return Err(format!("Unknown input source '{}' (available: terminal, bot, replay:FILE, \
+                                     script:MOVES)", name)),
+        };
+
+        Ok(source)
+    }
+
+    // a fresh source for a new game
+    pub fn open(&self, config: &GameConfig) -> Result<Box<dyn InputSource>, String> {
+        Ok(match *self {
+            Source::Terminal => Box::new(Terminal::new(config)),
+            Source::Bot => Box::new(Bot::new()),
+            Source::Replay(ref path) => Box::new(ReplayInput::new(&Replay::load(Path::new(path))?)),
+            Source::Script(ref moves) => Box::new(Script::parse(moves)?),
+        })
+    }
+}
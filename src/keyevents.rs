@@ -1,9 +1,14 @@
-use libc::{c_int, c_uint, c_short, c_uchar, STDIN_FILENO};
+use libc::{c_int, c_uint, c_short, c_uchar, size_t, ssize_t, EINTR, STDIN_FILENO, STDOUT_FILENO};
+use signals::suspend;
 use std::cmp::Ordering;
+use std::io::{self, Write};
+use std::str;
+use std::sync::{Mutex, MutexGuard, OnceLock};
 
 const NCHARS: usize = 32;   // ASCII chars 0-31
 const POLLIN: i16 = 1;      // represents the event for polling input
 const TCSANOW: i32 = 0;     // for setting the termios attributes immediately
+const ESC_TIMEOUT_MS: c_uint = 25;      // how long we wait for the rest of an escape sequence
 
 // Implementation based on <termios.h> (I did peek into a lot of stuff for getting the structure)
 // NOTE: All the fields are needed for proper communication with the foreign library!
@@ -35,21 +40,67 @@ extern "C" {
     // polling function (http://linux.die.net/man/2/poll)
     fn poll(file_desc: &mut PollFD, num_file_desc: c_int, timeout_ms: c_uint) -> c_int;
     // reading function (http://linux.die.net/man/2/read)
-    fn read(fd_num: c_int, buffer: &mut c_uint, count: c_uint) -> c_int;
+    fn read(fd_num: c_int, buffer: *mut u8, count: size_t) -> ssize_t;
+    // writing function (http://linux.die.net/man/2/write)
+    fn write(fd_num: c_int, buffer: *const u8, count: size_t) -> ssize_t;
 }
 
-pub struct TermiosAttribs {     // wrapper struct for the C-like struct
-    term: Termios               // created only for later drop
+struct Modes {      // terminal attributes (saved for restoring the terminal, even from signal handlers)
+    original: Termios,
+    raw: Termios,
+    mouse: bool,
+}
+
+static MODES: OnceLock<Modes> = OnceLock::new();
+
+// hide the cursor, and ask for key releases (and for all keys as escape sequences) from terminals
+// supporting the kitty keyboard protocol (others simply ignore this)
+const ENTER_RAW: &str = "\x1B[?25l\x1B[>11u";
+const ENTER_MOUSE: &str = "\x1B[?1003h\x1B[?1006h";   // report the pointer's movements (in SGR format)
+const LEAVE_RAW: &str = "\x1B[<u\x1B[0m\x1B[?25h";
+const LEAVE_MOUSE: &str = "\x1B[?1003l\x1B[?1006l";
+
+fn write_str(string: &str) {    // unbuffered (and safe to call from signal handlers, unlike `print!`)
+    unsafe { write(STDOUT_FILENO, string.as_ptr(), string.len()) };
+}
+
+pub struct TermiosAttribs {     // guard for restoring the terminal on drop
+    _private: (),
 }
 
 impl Drop for TermiosAttribs {
-    fn drop(&mut self) {    // override `drop` to set back the old termios attributes on drop
-        unsafe { tcsetattr(STDIN_FILENO, TCSANOW, &mut self.term) };
-        print!("\x1B[?25h");    // show the cursor
+    fn drop(&mut self) {
+        let _ = io::stdout().flush();   // whatever's left in the buffer should go out before we leave
+        restore_terminal();
+    }
+}
+
+// sets back the old termios attributes (and undoes everything else we've asked of the terminal)
+pub fn restore_terminal() {
+    if let Some(modes) = MODES.get() {
+        let mut term = modes.original.clone();
+        unsafe { tcsetattr(STDIN_FILENO, TCSANOW, &mut term) };
+        if modes.mouse {
+            write_str(LEAVE_MOUSE);
+        }
+
+        write_str(LEAVE_RAW);
     }
 }
 
-pub fn set_raw_mode() -> Result<TermiosAttribs, &'static str> {
+// switches back to raw mode (once we've been resumed after suspending)
+pub fn resume_raw_mode() {
+    if let Some(modes) = MODES.get() {
+        let mut term = modes.raw.clone();
+        unsafe { tcsetattr(STDIN_FILENO, TCSANOW, &mut term) };
+        write_str(ENTER_RAW);
+        if modes.mouse {
+            write_str(ENTER_MOUSE);
+        }
+    }
+}
+
+pub fn set_raw_mode(mouse: bool) -> Result<TermiosAttribs, &'static str> {
     let mut new_termios = Termios {     // some initial values
         c_iflag: 0,
         c_oflag: 0,
@@ -63,16 +114,21 @@ pub fn set_raw_mode() -> Result<TermiosAttribs, &'static str> {
 
     unsafe {
         let old_termios = match tcgetattr(STDIN_FILENO, &mut new_termios) { // try getting the old termios
-            0 => TermiosAttribs { term: new_termios.clone() },  // put it into the wrapper
+            0 => new_termios.clone(),
             _ => return Err("Can't get terminal attributes!"),
         };
 
         cfmakeraw(&mut new_termios);    // get the attributes for raw termios into our termios
         match tcsetattr(STDIN_FILENO, TCSANOW, &mut new_termios) {  // try setting the newly obtained attributes
-            0 => {  // Yay! switched to raw mode! Now, return the wrapper (for later drop)
-                print!("\x1B[?25l");    // hide the cursor
+            0 => {  // Yay! switched to raw mode! Now, return the guard (for later drop)
+                let _ = MODES.set(Modes { original: old_termios, raw: new_termios, mouse });
+                print!("{}", ENTER_RAW);
+                if mouse {
+                    print!("{}", ENTER_MOUSE);
+                }
+
                 println!("\n");
-                Ok(old_termios)
+                Ok(TermiosAttribs { _private: () })
             },
             _ => Err("Can't switch to raw mode!"),
         }
@@ -82,9 +138,17 @@ pub fn set_raw_mode() -> Result<TermiosAttribs, &'static str> {
 pub enum Poll {     // we need polling to capture the keystrokes in specific time intervals
     Start,
     Wait,
+    Interrupt,      // a signal arrived while we were waiting
 }
 
 pub fn poll_keypress(timeout_ms: c_uint) -> Result<Poll, &'static str> {
+    match decoder().is_pending() {  // keys that arrived together (like pasted stuff) could still be in the buffer
+        true => Ok(Poll::Start),
+        false => poll_input(timeout_ms),
+    }
+}
+
+fn poll_input(timeout_ms: c_uint) -> Result<Poll, &'static str> {
     let mut poll_fd = PollFD {
         fd: STDIN_FILENO,   // since we're capturing the standard input
         events: POLLIN,
@@ -95,34 +159,476 @@ pub fn poll_keypress(timeout_ms: c_uint) -> Result<Poll, &'static str> {
         match poll(&mut poll_fd, 1, timeout_ms).cmp(&0) {
             Ordering::Greater => Ok(Poll::Start),   // begin blocking to capture the keystroke
             Ordering::Equal => Ok(Poll::Wait),      // indicates that the poll has timed out
-            Ordering::Less => Err("Can't poll the input!"),
+            Ordering::Less => match io::Error::last_os_error().raw_os_error() {
+                Some(EINTR) => Ok(Poll::Interrupt),
+                _ => Err("Can't poll the input!"),
+            },
         }
     }
 }
 
-pub enum Key {      // We'll be needing only these keys for the game
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Key {
     Up,
     Down,
     Right,
     Left,
-    Quit,
-    Other,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Insert,
+    Delete,
+    F(u8),          // function keys (F1-F12)
+    Enter,
+    Tab,
+    Backspace,
+    Esc,
+    Char(char),     // printable characters (and letters pressed with Ctrl)
+    Mouse(usize, usize),    // mouse pointer's (column, row) in the terminal (starting from 1)
+    Other,          // anything we couldn't make sense of
 }
 
-pub fn read_keypress() -> Result<Key, &'static str> {
-    let mut buffer: u32 = 0;
-    unsafe {
-        if read(STDIN_FILENO, &mut buffer, 8) < 0 {
-            Err("Can't read the input!")
-        } else {
-            match buffer {
-                3 | 27 => Ok(Key::Quit),    // Ctrl-C & Esc
-                4283163 => Ok(Key::Up),
-                4348699 => Ok(Key::Down),
-                4414235 => Ok(Key::Right),
-                4479771 => Ok(Key::Left),
-                _ => Ok(Key::Other),
+impl Key {
+    // keys as they're named in the config file ("left", "enter", "a", etc.)
+    pub fn from_name(name: &str) -> Option<Key> {
+        let lower = name.to_lowercase();
+        match lower.as_str() {
+            "up" => Some(Key::Up),
+            "down" => Some(Key::Down),
+            "right" => Some(Key::Right),
+            "left" => Some(Key::Left),
+            "home" => Some(Key::Home),
+            "end" => Some(Key::End),
+            "pageup" => Some(Key::PageUp),
+            "pagedown" => Some(Key::PageDown),
+            "insert" => Some(Key::Insert),
+            "delete" => Some(Key::Delete),
+            "enter" => Some(Key::Enter),
+            "tab" => Some(Key::Tab),
+            "space" => Some(Key::Char(' ')),
+            "backspace" => Some(Key::Backspace),
+            "esc" | "escape" => Some(Key::Esc),
+            _ => match lower.starts_with('f') {
+                true if lower.len() > 1 => match lower[1..].parse() {
+                    Ok(num @ 1..=12) => Some(Key::F(num)),
+                    _ => None,
+                },
+                _ => {
+                    let mut chars = name.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) if !c.is_control() && !c.is_whitespace() => Some(Key::Char(c)),
+                        _ => None,
+                    }
+                },
+            },
+        }
+    }
+
+    // the other way around (for telling the player which key to press)
+    pub fn name(&self) -> String {
+        match *self {
+            Key::Char(' ') => "space".to_owned(),
+            Key::Char(c) => c.to_string(),
+            Key::F(num) => format!("f{}", num),
+            key => format!("{:?}", key).to_lowercase(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub alt: bool,
+    pub ctrl: bool,
+}
+
+impl Modifiers {
+    // xterm-style modifier parameter (1 + bitmask of shift, alt & ctrl)
+    fn from_param(param: u32) -> Modifiers {
+        let mask = param.saturating_sub(1);
+        Modifiers {
+            shift: mask & 1 != 0,
+            alt: mask & 2 != 0,
+            ctrl: mask & 4 != 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeyKind {
+    Press,
+    Repeat,     // the key's being held down
+    Release,    // only with the kitty keyboard protocol (other terminals don't tell us when a key's released)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeyEvent {
+    pub key: Key,
+    pub mods: Modifiers,
+    pub kind: KeyKind,
+}
+
+impl KeyEvent {
+    pub fn new(key: Key) -> KeyEvent {
+        KeyEvent::with(key, Modifiers::default())
+    }
+
+    fn with(key: Key, mods: Modifiers) -> KeyEvent {
+        KeyEvent { key, mods, kind: KeyKind::Press }
+    }
+
+    pub fn is_release(&self) -> bool {
+        self.kind == KeyKind::Release
+    }
+
+    // Esc & Ctrl-C always get you out of wherever you are
+    pub fn is_quit(&self) -> bool {
+        match self.key {
+            _ if self.is_release() => false,
+            Key::Esc => true,
+            Key::Char('c') => self.mods.ctrl,
+            _ => false,
+        }
+    }
+}
+
+enum Decoded {
+    Key(KeyEvent, usize),   // key and the number of bytes it took
+    Partial,                // the sequence hasn't fully arrived yet
+}
+
+// Turns the bytes coming from the terminal into keys. Terminals send most of the special keys as escape
+// sequences (`ESC [ A` for the up arrow, `ESC [ 1 ; 5 C` for Ctrl-Right, `ESC O P` for F1, etc.), which
+// can arrive in pieces, and a lone `ESC` is only an Esc keypress if nothing follows it for a while.
+#[derive(Debug, Default)]
+pub struct Decoder {
+    buffer: Vec<u8>,
+}
+
+impl Decoder {
+    pub const fn new() -> Decoder {
+        Decoder { buffer: Vec::new() }
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    // whether there's something (complete or not) left in the buffer
+    pub fn is_pending(&self) -> bool {
+        !self.buffer.is_empty()
+    }
+
+    // next complete key (if any)
+    pub fn decode(&mut self) -> Option<KeyEvent> {
+        match Decoder::parse(&self.buffer) {
+            Some(Decoded::Key(event, len)) => {
+                self.buffer.drain(..len);
+                Some(event)
+            },
+            _ => None,
+        }
+    }
+
+    // takes the incomplete stuff at the start of the buffer as it is (once we've waited long enough for the rest)
+    pub fn flush(&mut self) -> Option<KeyEvent> {
+        if let Some(event) = self.decode() {
+            return Some(event)
+        }
+
+        match self.buffer.first().cloned() {
+            Some(27) => {   // that was just Esc (whatever that follows will be decoded separately)
+                self.buffer.remove(0);
+                Some(KeyEvent::new(Key::Esc))
+            },
+            Some(_) => {    // broken UTF-8
+                self.buffer.clear();
+                Some(KeyEvent::new(Key::Other))
+            },
+            None => None,
+        }
+    }
+
+    fn parse(bytes: &[u8]) -> Option<Decoded> {
+        let first = *bytes.first()?;
+        let key = |key, len| Some(Decoded::Key(KeyEvent::new(key), len));
+        let ctrl = Modifiers { ctrl: true, ..Modifiers::default() };
+        match first {
+            27 => match bytes.get(1) {
+                None => Some(Decoded::Partial),
+                Some(&b'[') => Decoder::parse_csi(bytes),
+                Some(&b'O') => match bytes.get(2) {     // SS3 (arrows in "application mode" and F1-F4)
+                    None => Some(Decoded::Partial),
+                    Some(&final_byte) => Some(Decoded::Key(Decoder::special_key(final_byte, &[]), 3)),
+                },
+                Some(&27) => key(Key::Esc, 1),
+                Some(_) => match Decoder::parse(&bytes[1..])? {     // Alt + key
+                    Decoded::Key(mut event, len) => {
+                        event.mods.alt = true;
+                        Some(Decoded::Key(event, len + 1))
+                    },
+                    Decoded::Partial => Some(Decoded::Partial),
+                },
+            },
+            b'\r' | b'\n' => key(Key::Enter, 1),
+            b'\t' => key(Key::Tab, 1),
+            8 | 127 => key(Key::Backspace, 1),
+            0 => Some(Decoded::Key(KeyEvent::with(Key::Char(' '), ctrl), 1)),
+            1..=26 => Some(Decoded::Key(KeyEvent::with(Key::Char((first - 1 + b'a') as char), ctrl), 1)),
+            28..=31 => key(Key::Other, 1),
+            32..=126 => key(Key::Char(first as char), 1),
+            _ => {      // multi-byte UTF-8 character
+                let len = match first {
+                    0xC0..=0xDF => 2,
+                    0xE0..=0xEF => 3,
+                    0xF0..=0xF7 => 4,
+                    _ => return key(Key::Other, 1),
+                };
+
+                if bytes.len() < len {
+                    return Some(Decoded::Partial)
+                }
+
+                match str::from_utf8(&bytes[..len]).ok().and_then(|s| s.chars().next()) {
+                    Some(c) => key(Key::Char(c), len),
+                    None => key(Key::Other, len),
+                }
+            },
+        }
+    }
+
+    // `ESC [` followed by parameter bytes (digits, ';' and the likes of '?' and '<') and a final byte
+    fn parse_csi(bytes: &[u8]) -> Option<Decoded> {
+        let end = match bytes[2..].iter().position(|&b| !(0x20..0x40).contains(&b)) {
+            Some(idx) => idx + 2,
+            None => return Some(Decoded::Partial),
+        };
+
+        let final_byte = bytes[end];
+        if !(0x40..0x7F).contains(&final_byte) {    // not a proper sequence, so it's just an Alt-[
+            let mods = Modifiers { alt: true, ..Modifiers::default() };
+            return Some(Decoded::Key(KeyEvent::with(Key::Char('['), mods), 2))
+        }
+
+        let params = String::from_utf8_lossy(&bytes[2..end]);
+        let event = match params.starts_with(|c: char| !c.is_ascii_digit() && c != ';') {
+            true if params.starts_with('<') => Decoder::mouse(&params[1..], final_byte),
+            true => KeyEvent::new(Key::Other),      // other private sequences (we don't ask for any of those)
+            false => {
+                // parameters can have sub-parameters (like the `5:3` in `ESC [ 1 ; 5 : 3 D` for releasing Ctrl-Left)
+                let params = params.split(';')
+                                   .map(|p| p.split(':').map(|n| n.parse().unwrap_or(0)).collect())
+                                   .collect::<Vec<Vec<u32>>>();
+                Decoder::special_key(final_byte, &params)
+            },
+        };
+
+        Some(Decoded::Key(event, end + 1))
+    }
+
+    // SGR mouse reports (`ESC [ < button ; column ; row M`, with a lowercase `m` for releasing the button)
+    fn mouse(params: &str, final_byte: u8) -> KeyEvent {
+        let params = params.split(';').map(|p| p.parse().unwrap_or(0)).collect::<Vec<usize>>();
+        let (button, col, row) = match params.as_slice() {
+            &[button, col, row] if button & 64 == 0 => (button, col, row),  // (the wheel isn't of any use)
+            _ => return KeyEvent::new(Key::Other),
+        };
+
+        KeyEvent {
+            key: Key::Mouse(col, row),
+            mods: Modifiers {
+                shift: button & 4 != 0,
+                alt: button & 8 != 0,
+                ctrl: button & 16 != 0,
+            },
+            kind: match final_byte {
+                b'm' => KeyKind::Release,
+                _ if button & 32 != 0 => KeyKind::Repeat,   // moving around
+                _ => KeyKind::Press,
+            },
+        }
+    }
+
+    fn special_key(final_byte: u8, params: &[Vec<u32>]) -> KeyEvent {
+        let param = |i: usize, j: usize| params.get(i).and_then(|p| p.get(j)).cloned();
+        let mods = Modifiers::from_param(param(1, 0).unwrap_or(1));
+        let kind = match param(1, 1) {
+            Some(2) => KeyKind::Repeat,
+            Some(3) => KeyKind::Release,
+            _ => KeyKind::Press,
+        };
+
+        let key = match (final_byte, param(0, 0).unwrap_or(1)) {
+            (b'A', _) => Key::Up,
+            (b'B', _) => Key::Down,
+            (b'C', _) => Key::Right,
+            (b'D', _) => Key::Left,
+            (b'H', _) => Key::Home,
+            (b'F', _) => Key::End,
+            (b'P', _) => Key::F(1),
+            (b'Q', _) => Key::F(2),
+            (b'R', _) => Key::F(3),
+            (b'S', _) => Key::F(4),
+            (b'Z', _) => return KeyEvent::with(Key::Tab, Modifiers { shift: true, ..mods }),
+            (b'M', _) => Key::Enter,    // keypad Enter (in application mode)
+            (b'~', 1) | (b'~', 7) => Key::Home,
+            (b'~', 2) => Key::Insert,
+            (b'~', 3) => Key::Delete,
+            (b'~', 4) | (b'~', 8) => Key::End,
+            (b'~', 5) => Key::PageUp,
+            (b'~', 6) => Key::PageDown,
+            (b'~', num @ 11..=15) => Key::F((num - 10) as u8),
+            (b'~', num @ 17..=21) => Key::F((num - 11) as u8),
+            (b'~', num @ 23..=24) => Key::F((num - 12) as u8),
+            // kitty keyboard protocol (`ESC [ code ; modifiers u`, where code is the unicode codepoint)
+            (b'u', 13) => Key::Enter,
+            (b'u', 9) => Key::Tab,
+            (b'u', 8) | (b'u', 127) => Key::Backspace,
+            (b'u', 27) => Key::Esc,
+            (b'u', code) => match ::std::char::from_u32(code) {
+                Some(c) if !c.is_control() && code < 0xE000 => Key::Char(c),  // (beyond that are the keypad & modifier keys)
+                _ => Key::Other,
+            },
+            _ => Key::Other,
+        };
+
+        KeyEvent { key, mods, kind }
+    }
+}
+
+static DECODER: Mutex<Decoder> = Mutex::new(Decoder::new());
+
+fn decoder() -> MutexGuard<'static, Decoder> {
+    DECODER.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+fn read_input() -> Result<(), &'static str> {
+    let mut buffer = [0u8; 64];
+    match unsafe { read(STDIN_FILENO, buffer.as_mut_ptr(), buffer.len()) } {
+        n if n > 0 => {
+            decoder().feed(&buffer[..n as usize]);
+            Ok(())
+        },
+        0 => Err("The input has been closed!"),
+        _ => match io::Error::last_os_error().raw_os_error() {
+            Some(EINTR) => Ok(()),
+            _ => Err("Can't read the input!"),
+        },
+    }
+}
+
+// blocks until there's a key (so, this should be called only after polling)
+pub fn read_keypress() -> Result<KeyEvent, &'static str> {
+    loop {
+        if let Some(event) = decoder().decode() {
+            if event.key == Key::Char('z') && event.mods.ctrl && !event.is_release() {
+                suspend();      // Ctrl-Z doesn't stop us in raw mode, so we do it ourselves
             }
-        }   // the keycodes were found initially by pressing keys and printing `buffer`
+
+            return Ok(event)
+        }
+
+        if decoder().is_pending() {     // wait for the rest of the sequence (it's just Esc if nothing comes)
+            match poll_input(ESC_TIMEOUT_MS)? {
+                Poll::Start => read_input()?,
+                Poll::Wait | Poll::Interrupt => if let Some(event) = decoder().flush() {
+                    return Ok(event)
+                },
+            }
+        } else {
+            read_input()?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Decoded, Decoder, Key, KeyEvent, KeyKind, Modifiers};
+
+    // all the keys in the given bytes (and whatever's left to be flushed, like a lone Esc)
+    fn decode_all(bytes: &[u8]) -> Vec<KeyEvent> {
+        let mut decoder = Decoder::new();
+        decoder.feed(bytes);
+        let mut events = vec![];
+        while let Some(event) = decoder.flush() {
+            events.push(event);
+        }
+
+        events
+    }
+
+    fn mods(shift: bool, alt: bool, ctrl: bool) -> Modifiers {
+        Modifiers { shift, alt, ctrl }
+    }
+
+    #[test]
+    fn split_sequences_wait_for_the_rest() {
+        for bytes in &[&b"\x1B"[..], b"\x1B[", b"\x1B[1;5", b"\x1BO", b"\x1B[<0;10", b"\xE2\x82"] {
+            assert!(matches!(Decoder::parse(bytes), Some(Decoded::Partial)), "{:?} should be partial", bytes);
+        }
+
+        let mut decoder = Decoder::new();
+        decoder.feed(b"\x1B[1;");
+        assert_eq!(decoder.decode(), None);
+        assert!(decoder.is_pending());
+        decoder.feed(b"5C");
+        assert_eq!(decoder.decode(), Some(KeyEvent::with(Key::Right, mods(false, false, true))));
+        assert!(!decoder.is_pending());
+
+        decoder.feed(b"\xE2\x82");
+        assert_eq!(decoder.decode(), None);
+        decoder.feed(b"\xAC");
+        assert_eq!(decoder.decode(), Some(KeyEvent::new(Key::Char('€'))));
+    }
+
+    #[test]
+    fn lone_esc_after_timeout() {
+        let mut decoder = Decoder::new();
+        decoder.feed(b"\x1B");
+        assert_eq!(decoder.decode(), None);
+        assert_eq!(decoder.flush(), Some(KeyEvent::new(Key::Esc)));
+        assert!(!decoder.is_pending());
+
+        // (an incomplete sequence is taken as Esc, and the rest of it as the keys that they are)
+        assert_eq!(decode_all(b"\x1B["), vec![KeyEvent::new(Key::Esc), KeyEvent::new(Key::Char('['))]);
+        assert_eq!(decode_all(b"\x1B\x1B"), vec![KeyEvent::new(Key::Esc), KeyEvent::new(Key::Esc)]);
+    }
+
+    #[test]
+    fn alt_keys() {
+        assert_eq!(decode_all(b"\x1B[\r"), vec![KeyEvent::with(Key::Char('['), mods(false, true, false)),
+                                                KeyEvent::new(Key::Enter)]);
+        assert_eq!(decode_all(b"\x1Bx"), vec![KeyEvent::with(Key::Char('x'), mods(false, true, false))]);
+        assert_eq!(decode_all(b"\x1B\x03"), vec![KeyEvent::with(Key::Char('c'), mods(false, true, true))]);
+    }
+
+    #[test]
+    fn modified_arrows() {
+        assert_eq!(decode_all(b"\x1B[A\x1BOB"), vec![KeyEvent::new(Key::Up), KeyEvent::new(Key::Down)]);
+        assert_eq!(decode_all(b"\x1B[1;2C"), vec![KeyEvent::with(Key::Right, mods(true, false, false))]);
+        assert_eq!(decode_all(b"\x1B[1;3D"), vec![KeyEvent::with(Key::Left, mods(false, true, false))]);
+        assert_eq!(decode_all(b"\x1B[1;8A"), vec![KeyEvent::with(Key::Up, mods(true, true, true))]);
+        assert_eq!(decode_all(b"\x1B[5;5~"), vec![KeyEvent::with(Key::PageUp, mods(false, false, true))]);
+    }
+
+    #[test]
+    fn key_releases_and_repeats() {
+        let release = decode_all(b"\x1B[1;5:3D");
+        assert_eq!(release, vec![KeyEvent { key: Key::Left, mods: mods(false, false, true), kind: KeyKind::Release }]);
+        assert!(release[0].is_release() && !release[0].is_quit());
+
+        let repeat = decode_all(b"\x1B[97;1:2u");
+        assert_eq!(repeat, vec![KeyEvent { key: Key::Char('a'), mods: Modifiers::default(), kind: KeyKind::Repeat }]);
+    }
+
+    #[test]
+    fn sgr_mouse() {
+        let event = |col, row, mods, kind| KeyEvent { key: Key::Mouse(col, row), mods, kind };
+        assert_eq!(decode_all(b"\x1B[<0;10;5M"), vec![event(10, 5, Modifiers::default(), KeyKind::Press)]);
+        assert_eq!(decode_all(b"\x1B[<0;10;5m"), vec![event(10, 5, Modifiers::default(), KeyKind::Release)]);
+        assert_eq!(decode_all(b"\x1B[<35;12;7M"), vec![event(12, 7, Modifiers::default(), KeyKind::Repeat)]);
+        assert_eq!(decode_all(b"\x1B[<20;1;1M"), vec![event(1, 1, mods(true, false, true), KeyKind::Press)]);
+        assert_eq!(decode_all(b"\x1B[<64;3;3M"), vec![KeyEvent::new(Key::Other)]);     // (the wheel)
     }
 }
@@ -0,0 +1,26 @@
+extern crate libc;
+pub extern crate rand;     // (for the RNG that the obstacle generators get)
+
+pub mod bindings;
+pub mod bot;
+pub mod config;
+pub mod frame;
+pub mod helpers;
+pub mod input;
+pub mod keyevents;
+pub mod menu;
+pub mod mouse;
+pub mod obstacles;
+pub mod options;
+pub mod reach;
+pub mod repeat;
+pub mod replay;
+pub mod scores;
+pub mod screen;
+pub mod signals;
+pub mod sprites;
+
+pub use config::GameConfig;
+pub use helpers::FallArea;
+pub use obstacles::ObstacleGenerator;
+pub use sprites::{BodyPart, Cliff, Coin, Collision, FrameState, Game, Input, Jumper, Points, Power, PowerUp};
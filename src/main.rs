@@ -1,81 +1,618 @@
+extern crate free_fall;
 extern crate libc;
-extern crate rand;
 extern crate time;
 
-mod helpers;
-mod keyevents;
-mod sprites;
-
-use helpers::print_msg;
-use keyevents::*;
+use free_fall::bindings::Action;
+use free_fall::config::{GameConfig, Profile};
+use free_fall::helpers::{print_msg, random_seed, window_size, FallArea, MS};
+use free_fall::input::{InputSource, ReplayInput, Source};
+use free_fall::frame::{Cell, Color, Entity, Frame};
+use free_fall::keyevents::*;
+use free_fall::menu::{Menu, MenuAction};
+use free_fall::options::Options;
+use free_fall::replay::Replay;
+use free_fall::scores::{HighScores, Score, NAME_LENGTH};
+use free_fall::screen::Screen;
+use free_fall::signals::{take_resize, take_resume, watch_signals};
+use free_fall::sprites::{FrameState, Game, Input};
 use libc::c_uint;
-use sprites::Game;
+use std::env;
+use std::path::Path;
 use time::precise_time_ns;
 
-// width & height for game
-const WIDTH: usize = 50;
-const HEIGHT: usize = 30;
-// initial difficulty setting (game speed & cliff rate)
-const TIMEOUT_MS: c_uint = 80;
-const CLIFF_SEPARATION: usize = 8;
-// shift the jumper or cliff by X chars (change this if you change the width & height)
-const JUMPER_X: usize = 3;
-const JUMPER_Y: usize = 1;       // 2-DOF won't be realistic and so, let's abandon it!
-const CLIFF_Y: usize = 1;
+const MAX_REPLAY_SPEED: c_uint = 8;     // fast-forwarding beyond this is pointless (the terminal can't keep up)
+const RESIZE_POLL_MS: c_uint = 500;     // how often we check the window size while it's too small
+const FRAME_NS: u64 = 16 * MS;          // frames are drawn at ~60 FPS (at most)
+const MAX_CATCH_UP: usize = 5;          // ticks we're allowed to make up for in one go
+// stuff that can be changed from the settings menu (applies to new games) along with their steps
+const MENU_SETTINGS: [(&str, &str, isize); 2] = [
+    ("Speed", "timeout", -10),      // lower timeout is faster
+    ("Cliff separation", "separation", 1),
+];
+
+enum Outcome {      // what happens once a game is done
+    Restart,
+    Title,
+    Quit,
+}
 
 fn main() {
-    let _raw = match set_raw_mode() {   // old termios attributes (which will be restored on drop)
-        Ok(term_attrib) => term_attrib,
+    let options = match Options::parse(env::args().skip(1)) {
+        Ok(options) => options,
         Err(err) => {
-            print_msg(err, None);
+            print_msg(&err, None);
             return;
         }
     };
 
-    let mut game = match Game::new(TIMEOUT_MS, CLIFF_SEPARATION) {
-        Ok(stuff) => stuff,
+    if options.help {
+        print!("{}", Options::usage());
+        return;
+    }
+
+    let profile = match options.load_profile() {
+        Ok(profile) => profile,
         Err(err) => {
             print_msg(&err, None);
             return;
         }
     };
-    let mut poll_timeout_ms = game.poll_timeout;
-    let mut time_since_last_ns: u64 = 0;
-
-    while game.is_running() {
-        let start_time = precise_time_ns();
-        match poll_keypress(poll_timeout_ms) {      // wait for the given time to capture the input
-            Ok(poll) => match poll {
-                Poll::Start => {
-                    let keypress = read_keypress();
-                    match keypress {        // proceeds immediately on input
-                        Ok(key) => match key {
-                            Key::Quit => {
-                                print_msg("\r\tGoodbye!\n\r", Some("B"));
-                                break
-                            },
-                            _ => {
-                                time_since_last_ns += precise_time_ns() - start_time;
-                                poll_timeout_ms = game.poll_timeout - ((time_since_last_ns / 1000000) as c_uint);
-                                game.jumper_shift(key)
-                            },
-                        },
-                        Err(err) => {
-                            print_msg(err, None);
+
+    let _raw = match set_raw_mode(profile.mouse && options.replay.is_none()) {   // old termios attributes (which will be restored on drop)
+        Ok(term_attrib) => term_attrib,
+        Err(err) => {
+            print_msg(err, None);
+            return;
+        }
+    };
+
+    watch_signals();
+    let result = match options.replay {
+        Some(ref path) => replay(Path::new(path)),
+        None => run(&options, &profile),
+    };
+
+    if let Err(err) = result {
+        print_msg(&err, None);
+    }
+}
+
+fn game_over(screen: &mut Screen, game: &Game, state: &FrameState) -> bool {
+    match state.collision {
+        Some(collision) => {
+            screen.print_msg(collision.message(), Some("Y"));
+            screen.print_msg(&format!("SCORE: {}", state.points.breakdown()), Some("G"));
+            screen.print_msg(&format!("SEED: {} (use '--seed {}' to replay this game)", game.seed(), game.seed()), Some("B"));
+            true
+        },
+        None => false,
+    }
+}
+
+// waits for the player to play again, go back to the title or quit (once the game's over)
+fn after_game(screen: &mut Screen, profile: &Profile, game: &Game, state: &FrameState) -> Result<Outcome, String> {
+    let key = |action| profile.bindings.keys(action).first().map(Key::name).unwrap_or_default();
+    let status = format!("GAME OVER  [{}] play again  [enter] title  [{}] quit", key(Action::Restart), key(Action::Quit));
+    loop {
+        if take_resize() {
+            if !fit_screen(screen, game.area())? {
+                screen.print_msg("Goodbye!", Some("B"));
+                return Ok(Outcome::Quit)
+            }
+
+            screen.print_frame(state);
+            game_over(screen, game, state);
+        }
+
+        screen.print_status(&status, Some("B"));
+        if let Poll::Start = poll_keypress(RESIZE_POLL_MS)? {
+            let event = read_keypress()?;
+            match profile.bindings.action(event) {
+                _ if event.is_release() => (),
+                Some(Action::Restart) => return Ok(Outcome::Restart),
+                Some(Action::Quit) => {
+                    screen.print_msg("Goodbye!", Some("B"));
+                    return Ok(Outcome::Quit)
+                },
+                _ if event.key == Key::Enter => return Ok(Outcome::Title),
+                _ => (),
+            }
+        }
+    }
+}
+
+// keeps the game paused while the window's too small for it (returns `false` if the player quits meanwhile)
+fn fit_screen(screen: &mut Screen, area: FallArea) -> Result<bool, String> {
+    loop {
+        take_resize();
+        let (rows, cols) = window_size()?;
+        match area.relayout(rows, cols) {
+            Ok(new_area) => {
+                screen.resize(new_area);
+                return Ok(true)
+            },
+            Err(_) => screen.print_too_small(rows, cols),
+        }
+
+        // wait for the next resize (the poll will be interrupted) or a keypress
+        if let Poll::Start = poll_keypress(RESIZE_POLL_MS)? {
+            if read_keypress()?.is_quit() {
+                return Ok(false)
+            }
+        }
+    }
+}
+
+fn run(options: &Options, profile: &Profile) -> Result<(), String> {
+    let mut config = profile.config;
+    let area = FallArea::new(&config)?;
+    let mut screen = Screen::new(area);
+    let mut scores = match HighScores::default_path().map(|path| HighScores::load(&path)) {
+        Some(Ok(scores)) => Some(scores),
+        Some(Err(err)) => {     // (the game's still playable without them)
+            screen.show_notice(&err);
+            None
+        },
+        None => None,       // no place for keeping the scores
+    };
+
+    screen.show_best(scores.as_ref().and_then(HighScores::best).map(|best| best.score));
+    while title(&mut screen, area, &mut config, scores.as_ref())? {
+        loop {
+            match play(&mut screen, area, options, profile, &mut config, scores.as_mut())? {
+                Outcome::Restart => continue,
+                Outcome::Title => break,
+                Outcome::Quit => return Ok(()),
+            }
+        }
+    }
+
+    screen.print_msg("Goodbye!", Some("B"));
+    Ok(())
+}
+
+// shows the menu over the given frame until the player does something (`None` if they quit meanwhile)
+fn show_menu(screen: &mut Screen, area: FallArea, menu: &mut Menu, background: &Frame)
+             -> Result<Option<MenuAction>, String> {
+    screen.redraw();    // the whole frame's changing anyway (and the status lines should go away)
+    loop {
+        if take_resize() && !fit_screen(screen, area)? {
+            return Ok(None)
+        }
+
+        let mut frame = background.clone();
+        menu.draw(&mut frame);
+        screen.draw(&frame);
+
+        match poll_keypress(RESIZE_POLL_MS)? {
+            Poll::Start => match menu.handle(read_keypress()?) {
+                Some(action) => return Ok(Some(action)),
+                None => continue,
+            },
+            _ => continue,
+        }
+    }
+}
+
+fn settings_menu(screen: &mut Screen, area: FallArea, config: &mut GameConfig, background: &Frame)
+                 -> Result<bool, String> {
+    let mut items = MENU_SETTINGS.iter().map(|_| "").collect::<Vec<_>>();
+    items.push("Back");
+    let mut menu = Menu::new("SETTINGS", &items);
+    loop {
+        for (i, &(label, name, _)) in MENU_SETTINGS.iter().enumerate() {
+            menu.set_item(i, &format!("{}: < {} >", label, config.get(name).unwrap_or(0)));
+        }
+
+        match show_menu(screen, area, &mut menu, background)? {
+            Some(MenuAction::Adjust(i, direction)) if i < MENU_SETTINGS.len() => {
+                let (_, name, step) = MENU_SETTINGS[i];
+                let (min, max) = GameConfig::limits(name).unwrap_or((0, 0));
+                let value = config.get(name).unwrap_or(0) as isize + direction * step;
+                let mut new_config = *config;
+                let _ = new_config.set(name, value.max(min as isize).min(max as isize) as usize);
+                if new_config.validate().is_ok() {      // separation can't go beyond the height, for example
+                    *config = new_config;
+                }
+            },
+            Some(MenuAction::Choose(i)) if i == MENU_SETTINGS.len() => return Ok(true),
+            Some(MenuAction::Back) => return Ok(true),
+            Some(_) => continue,
+            None => return Ok(false),
+        }
+    }
+}
+
+// title screen (returns `false` when the player wants to leave)
+fn title(screen: &mut Screen, area: FallArea, config: &mut GameConfig, scores: Option<&HighScores>)
+         -> Result<bool, String> {
+    let mut background = Frame::new(area.width.0, area.height.0);
+    let mut style = Cell::new(' ', Color::Yellow, Entity::Empty);
+    style.attrs.bold = true;
+    let banner = "F R E E   F A L L";
+    background.put_str((area.width.0 - banner.len()) / 2, area.height.0 / 5, banner, style);
+
+    let mut menu = Menu::new("Dodge the cliffs!", &["Play", "High scores", "Settings", "Quit"]);
+    loop {
+        match show_menu(screen, area, &mut menu, &background)? {
+            Some(MenuAction::Choose(0)) => return Ok(true),
+            Some(MenuAction::Choose(1)) => match scores {
+                Some(scores) if !show_scores(screen, area, scores, &background, None)? => return Ok(false),
+                _ => continue,
+            },
+            Some(MenuAction::Choose(2)) => if !settings_menu(screen, area, config, &background)? {
+                return Ok(false)
+            },
+            Some(MenuAction::Choose(_)) | Some(MenuAction::Back) | None => return Ok(false),
+            Some(MenuAction::Adjust(..)) => continue,
+        }
+    }
+}
+
+// pause menu (shown over the dimmed game) which decides how the game goes on
+fn pause_menu(screen: &mut Screen, area: FallArea, config: &mut GameConfig, state: &FrameState)
+              -> Result<Option<Outcome>, String> {
+    let mut background = state.frame.clone();
+    background.dim();
+    let mut menu = Menu::new("PAUSED", &["Resume", "Restart", "Settings", "Quit to title"]);
+    loop {
+        return match show_menu(screen, area, &mut menu, &background)? {
+            Some(MenuAction::Choose(0)) | Some(MenuAction::Back) => Ok(None),
+            Some(MenuAction::Choose(1)) => Ok(Some(Outcome::Restart)),
+            Some(MenuAction::Choose(2)) => match settings_menu(screen, area, config, &background)? {
+                true => continue,
+                false => Ok(Some(Outcome::Quit)),
+            },
+            Some(MenuAction::Choose(_)) => Ok(Some(Outcome::Title)),
+            Some(MenuAction::Adjust(..)) => continue,
+            None => Ok(Some(Outcome::Quit)),
+        }
+    }
+}
+
+// high score table (with the given rank highlighted), which stays until the player presses something
+fn show_scores(screen: &mut Screen, area: FallArea, scores: &HighScores, background: &Frame, rank: Option<usize>)
+               -> Result<bool, String> {
+    let mut rows = scores.entries().iter().enumerate().map(|(i, entry)| {
+        format!("{:>2}. {:<3} {:>5} pts {:>3} spd  {}", i + 1, entry.name, entry.score, entry.max_speed, entry.date)
+    }).collect::<Vec<_>>();
+
+    if rows.is_empty() {
+        rows.push("Nobody's made it yet!".to_owned());
+    }
+
+    let rows = rows.iter().map(String::as_str).collect::<Vec<_>>();
+    let mut menu = Menu::new("HIGH SCORES", &rows);
+    menu.select(rank.unwrap_or(0));
+    Ok(show_menu(screen, area, &mut menu, background)?.is_some())
+}
+
+// asks for the player's initials (`None` if they don't want to be in the table)
+fn read_initials(screen: &mut Screen) -> Result<Option<String>, String> {
+    let mut name = String::new();
+    loop {
+        let blanks = (name.len()..NAME_LENGTH).map(|_| "_").collect::<String>();
+        screen.print_status(&format!("NEW HIGH SCORE! Enter your initials: {}{}", name, blanks), Some("Y"));
+        if let Poll::Start = poll_keypress(RESIZE_POLL_MS)? {
+            let event = read_keypress()?;
+            match event.key {
+                _ if event.is_quit() => return Ok(None),
+                _ if event.is_release() => (),
+                Key::Char(c) if c.is_ascii_alphanumeric() && name.len() < NAME_LENGTH => {
+                    name.push(c.to_ascii_uppercase());
+                },
+                Key::Backspace => {
+                    name.pop();
+                },
+                Key::Enter if !name.is_empty() => return Ok(Some(name)),
+                _ => (),
+            }
+        }
+    }
+}
+
+fn record_score(screen: &mut Screen, area: FallArea, scores: &mut HighScores, game: &Game, state: &FrameState)
+                -> Result<(), String> {
+    if !scores.qualifies(state.score) {
+        return Ok(())
+    }
+
+    let name = match read_initials(screen)? {
+        Some(name) => name,
+        None => return Ok(()),
+    };
+
+    let rank = scores.insert(Score {
+        name,
+        score: state.score,
+        max_speed: state.speed,
+        seed: game.seed(),
+        date: time::now().strftime("%Y-%m-%d").map(|date| date.to_string()).unwrap_or_default(),
+        cause: state.collision.map(|collision| collision.message()).unwrap_or_default().to_owned(),
+    });
+
+    if let Err(err) = scores.save() {   // (they're still kept for this session)
+        screen.show_notice(&err);
+    }
+
+    screen.show_best(scores.best().map(|best| best.score));
+    let mut background = state.frame.clone();
+    background.dim();
+    show_scores(screen, area, scores, &background, rank)?;
+    Ok(())
+}
+
+// feeds the input to the game (and to the recording, if there's one)
+fn advance(game: &mut Game, recording: &mut Option<Replay>, input: Input) -> FrameState {
+    if let Some(ref mut recording) = *recording {
+        recording.record(game.ticks(), input);
+    }
+
+    game.step(input)
+}
+
+// feeds the moves that the source has for us right now (returns the last frame, if there were any)
+fn follow(game: &mut Game, recording: &mut Option<Replay>, source: &mut dyn InputSource, now: u64)
+          -> Option<FrameState> {
+    let mut state = None;
+    while let Some(key) = source.next_move(game, now) {
+        let new_state = advance(game, recording, Input::Key(key));
+        let over = new_state.is_over();
+        state = Some(new_state);
+        if over {
+            break
+        }
+    }
+
+    state
+}
+
+// Fixed-timestep loop: the time that has passed is collected in an accumulator, which is then spent on
+// exactly as many ticks as it can pay for. Input is drained (and fed to the game) as soon as it arrives,
+// and the frames are drawn at their own rate, so neither of them can hurry or hold back the cliffs.
+fn play(screen: &mut Screen, area: FallArea, options: &Options, profile: &Profile, config: &mut GameConfig,
+        mut scores: Option<&mut HighScores>) -> Result<Outcome, String> {
+    let mut source = options.input.open(config)?;
+    // recordings are played with the settings that they were made with (whatever the profile says)
+    let (outer, mut recorded) = (area, None);
+    let (area, config) = match source.config() {
+        Some(settings) => {
+            let area = FallArea::new(&settings)?;
+            screen.resize(area);
+            (area, recorded.insert(settings))
+        },
+        None => (area, config),
+    };
+
+    let seed = options.seed.or_else(|| source.seed()).unwrap_or_else(random_seed);
+    let mut game = Game::with_area(area, *config, seed);
+    let mut recording = options.record.as_ref().map(|_| Replay::new(game.seed(), *config));
+
+    screen.redraw();
+    let mut state = game.draw();
+    let (mut last_time, mut accumulator, mut next_frame) = (precise_time_ns(), 0, 0);
+    let mut dirty = true;   // whether there's something new to be drawn
+    take_resume();      // (being suspended before the game doesn't count)
+
+    let outcome = 'game: loop {
+        let mut paused = false;
+        if take_resize() {
+            if !fit_screen(screen, game.area())? {
+                screen.print_msg("Goodbye!", Some("B"));
+                break Outcome::Quit
+            }
+
+            paused = true;
+        }
+
+        if take_resume() && !state.is_over() {     // we've been suspended, so the player gets some time to get back
+            screen.print_frame(&state);
+            if let Some(outcome) = pause_menu(screen, area, config, &state)? {
+                break outcome
+            }
+
+            paused = true;
+        }
+
+        if paused {     // the clock starts afresh (and the time spent so far is lost)
+            last_time = precise_time_ns();
+            accumulator = 0;
+            source.clear();
+            dirty = true;
+        }
+
+        let now = precise_time_ns();
+        accumulator += now.saturating_sub(last_time);
+        last_time = now;
+
+        let mut ticks = 0;
+        while !state.is_over() && accumulator >= tick_ns(&game) {
+            accumulator -= tick_ns(&game);
+            if let Some(new_state) = follow(&mut game, &mut recording, &mut *source, now) {
+                state = new_state;      // (everything meant for this tick comes before it)
+                if state.is_over() {
+                    break
+                }
+            }
+
+            state = advance(&mut game, &mut recording, Input::Tick);
+            dirty = true;
+            ticks += 1;
+            if ticks == MAX_CATCH_UP {      // we're way behind (we'll never catch up if we keep trying)
+                accumulator = 0;
+            }
+        }
+
+        if !state.is_over() {
+            if let Some(new_state) = follow(&mut game, &mut recording, &mut *source, now) {
+                state = new_state;
+                dirty = true;
+            }
+        }
+
+        if state.is_over() {
+            screen.print_frame(&state);
+            match scores {      // (only people make it to the table)
+                Some(ref mut scores) if options.input == Source::Terminal => {
+                    record_score(screen, area, scores, &game, &state)?;
+                    screen.print_frame(&state);     // back to where the jumper died
+                },
+                _ => (),
+            }
+
+            game_over(screen, &game, &state);
+            break after_game(screen, profile, &game, &state)?
+        }
+
+        if dirty && now >= next_frame {
+            screen.print_frame(&state);
+            next_frame = now + FRAME_NS;
+            dirty = false;
+        }
+
+        // sleep until the next tick, frame or move (whichever's first), unless there's some input
+        let mut wait_ns = tick_ns(&game).saturating_sub(accumulator);
+        if dirty {
+            wait_ns = wait_ns.min(next_frame.saturating_sub(now));
+        }
+
+        if let Some(ms) = source.wait_ms(&game, now) {
+            wait_ns = wait_ns.min(ms as u64 * MS);
+        }
+
+        let mut timeout_ms = wait_ns.div_ceil(MS).min(c_uint::MAX as u64) as c_uint;
+        while let Poll::Start = poll_keypress(timeout_ms)? {
+            timeout_ms = 0;     // drain whatever's left without waiting
+            let (event, now) = (read_keypress()?, precise_time_ns());
+            let pressed = event.kind == KeyKind::Press;     // (releases only matter for the held keys)
+            let action = profile.bindings.action(event);
+            match event.key {
+                Key::Mouse(col, _) if profile.mouse => source.point(Some(screen.column(col))),
+                _ => (),
+            }
+
+            match action {
+                Some(Action::Quit) if pressed => {
+                    screen.print_msg("Goodbye!", Some("B"));
+                    break 'game Outcome::Quit
+                },
+                Some(Action::Restart) if pressed => break 'game Outcome::Restart,
+                Some(Action::Pause) if pressed => {     // everything's frozen until the player's done with the menu
+                    if let Some(outcome) = pause_menu(screen, area, config, &state)? {
+                        break 'game outcome
+                    }
+
+                    last_time = precise_time_ns();
+                    accumulator = 0;
+                    source.clear();
+                    dirty = true;
+                    break
+                },
+                action => if let Some(key) = action.and_then(|action| action.key()) {
+                    source.press(key, event.kind, now);
+                    if let Some(new_state) = follow(&mut game, &mut recording, &mut *source, now) {
+                        state = new_state;
+                        dirty = true;
+                        if state.is_over() {
                             break
                         }
                     }
                 },
-                Poll::Wait => {
-                    time_since_last_ns = 0;
-                    poll_timeout_ms = game.poll_timeout;
-                    game.cliffs_shift();
+            }
+        }
+    };
+
+    if let (Some(recording), Some(path)) = (recording, options.record.as_ref()) {
+        recording.save(Path::new(path))?;
+    }
+
+    if area.width.0 != outer.width.0 || area.height.0 != outer.height.0 {
+        screen.resize(outer);   // (back to the box that the menus are drawn in)
+    }
+
+    Ok(outcome)
+}
+
+// time between the ticks right now (the game gets faster as the score goes up)
+fn tick_ns(game: &Game) -> u64 {
+    game.poll_timeout.max(1) as u64 * MS
+}
+
+// plays back a recorded game, which can be paused (space), fast-forwarded (f) and stepped through (n)
+fn replay(path: &Path) -> Result<(), String> {
+    let replay = Replay::load(path)?;
+    let mut game = Game::new(replay.config, replay.seed)?;
+    let mut source = ReplayInput::new(&replay);
+
+    let mut screen = Screen::new(game.area());
+    let mut state = game.draw();
+    let (mut paused, mut speed) = (false, 1);
+    let (mut last_time, mut accumulator, mut ticks) = (precise_time_ns(), 0, 0);
+
+    loop {
+        if take_resize() && !fit_screen(&mut screen, game.area())? {
+            screen.print_msg("Goodbye!", Some("B"));
+            break
+        }
+
+        paused |= take_resume();
+        let now = precise_time_ns();
+        if !paused {    // (same as in the game, but the ticks are shorter when fast-forwarding)
+            accumulator += now.saturating_sub(last_time);
+            while accumulator >= tick_ns(&game) / speed as u64 && ticks < MAX_CATCH_UP {
+                accumulator -= tick_ns(&game) / speed as u64;
+                ticks += 1;
+            }
+        }
+
+        last_time = now;
+        let mut ended = false;
+        while ticks > 0 && !ended {     // feed everything that happened in the next tick
+            ticks -= 1;
+            if let Some(new_state) = follow(&mut game, &mut None, &mut source, now) {
+                state = new_state;
+            }
+
+            ended = source.finished(&game);
+            if !ended && !state.is_over() {
+                state = game.step(Input::Tick);
+            }
+        }
+
+        screen.print_frame(&state);
+        if game_over(&mut screen, &game, &state) {
+            break
+        } else if ended {
+            screen.print_msg("End of replay!", Some("B"));
+            break
+        }
+
+        let status = if paused { "PAUSED".to_owned() } else { format!("x{}", speed) };
+        screen.print_status(&format!("REPLAY ({})  [space] pause  [f] fast-forward  [n] next tick  [esc] quit", status),
+                            Some("B"));
+
+        // paused replays simply keep polling until something happens
+        let wait_ns = (tick_ns(&game) / speed as u64).saturating_sub(accumulator);
+        let timeout_ms = if paused { RESIZE_POLL_MS } else { wait_ns.div_ceil(MS) as c_uint };
+        if let Poll::Start = poll_keypress(timeout_ms)? {
+            match read_keypress()? {
+                event if event.is_quit() => {
+                    screen.print_msg("Goodbye!", Some("B"));
+                    break
+                },
+                event if event.is_release() => (),
+                KeyEvent { key: Key::Char(' '), .. } => {
+                    paused = !paused;
+                    accumulator = 0;
                 },
-            },
-            Err(err) => {
-                print_msg(err, None);
-                break
+                KeyEvent { key: Key::Char('f'), .. } => {
+                    speed = if speed < MAX_REPLAY_SPEED { speed * 2 } else { 1 };
+                },
+                KeyEvent { key: Key::Char('n'), .. } if paused => ticks += 1,
+                _ => (),
             }
         }
     }
+
+    Ok(())
 }
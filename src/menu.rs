@@ -0,0 +1,100 @@
+use frame::{Cell, Color, Entity, Frame};
+use helpers::multiply;
+use keyevents::{Key, KeyEvent};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MenuAction {
+    Choose(usize),          // player has picked an item
+    Adjust(usize, isize),   // player wants to change the item's value (left is -1, right is +1)
+    Back,
+}
+
+#[derive(Clone, Debug)]
+pub struct Menu {   // a small list of items drawn (in a box) at the center of the frame
+    title: String,
+    items: Vec<String>,
+    selected: usize,
+}
+
+impl Menu {
+    pub fn new(title: &str, items: &[&str]) -> Menu {
+        Menu {
+            title: title.to_owned(),
+            items: items.iter().map(|&item| item.to_owned()).collect(),
+            selected: 0,
+        }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn select(&mut self, index: usize) {
+        if index < self.items.len() {
+            self.selected = index;
+        }
+    }
+
+    pub fn set_item(&mut self, index: usize, label: &str) {
+        if let Some(item) = self.items.get_mut(index) {
+            *item = label.to_owned();
+        }
+    }
+
+    // moves the selection around (and returns whatever the player wants to do, if anything)
+    pub fn handle(&mut self, event: KeyEvent) -> Option<MenuAction> {
+        if event.is_quit() {
+            return Some(MenuAction::Back)
+        } else if event.is_release() {
+            return None
+        }
+
+        let num_items = self.items.len();
+        match event.key {
+            Key::Up => self.selected = (self.selected + num_items - 1) % num_items,
+            Key::Down => self.selected = (self.selected + 1) % num_items,
+            Key::Left => return Some(MenuAction::Adjust(self.selected, -1)),
+            Key::Right => return Some(MenuAction::Adjust(self.selected, 1)),
+            Key::Enter | Key::Char(' ') => return Some(MenuAction::Choose(self.selected)),
+            _ => (),
+        }
+
+        None
+    }
+
+    pub fn draw(&self, frame: &mut Frame) {
+        let text_width = self.items.iter()
+                                   .chain(Some(&self.title))
+                                   .map(|item| item.chars().count())
+                                   .max()
+                                   .unwrap_or(0);
+        let (width, height) = (text_width + 8, self.items.len() + 4);   // border, padding & the title
+        let (frame_width, frame_height) = frame.size();
+        let x_pos = frame_width.saturating_sub(width) / 2;
+        let y_pos = frame_height.saturating_sub(height) / 2;
+
+        let style = Cell::new(' ', Color::White, Entity::Empty);
+        let dashes = "+".to_owned() + &multiply("-", width - 2) + "+";
+        let blank = "|".to_owned() + &multiply(" ", width - 2) + "|";
+        let put_centered = |frame: &mut Frame, y: usize, text: &str, text_style: Cell| {
+            frame.put_str(x_pos, y, &blank, style);
+            frame.put_str(x_pos + (width - text.chars().count()) / 2, y, text, text_style);
+        };
+
+        let (mut title_style, mut selected_style) = (style, style);
+        title_style.attrs.bold = true;
+        selected_style.attrs.reverse = true;
+
+        frame.put_str(x_pos, y_pos, &dashes, style);
+        put_centered(frame, y_pos + 1, &self.title, title_style);
+        put_centered(frame, y_pos + 2, "", style);
+        for (i, item) in self.items.iter().enumerate() {
+            match i == self.selected {
+                true => put_centered(frame, y_pos + 3 + i, &format!("> {} <", item), selected_style),
+                false => put_centered(frame, y_pos + 3 + i, item, style),
+            }
+        }
+
+        frame.put_str(x_pos, y_pos + height - 1, &dashes, style);
+    }
+}
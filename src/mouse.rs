@@ -0,0 +1,52 @@
+use config::GameConfig;
+use helpers::MS;
+use keyevents::Key;
+use libc::c_uint;
+use sprites::Jumper;
+
+// Steers the jumper towards the mouse pointer. It presses one key at a time (at the rate of held keys),
+// so the jumper glides over to the pointer's column instead of teleporting there (and with momentum, it
+// brakes on the way so that the jumper doesn't fly past the pointer).
+#[derive(Clone, Debug)]
+pub struct Steering {
+    rate: u64,
+    target: Option<usize>,      // column (in the fall area) that the jumper's center should reach
+    next: u64,                  // time for the next move
+}
+
+impl Steering {
+    pub fn new(config: &GameConfig) -> Steering {
+        Steering {
+            rate: config.repeat as u64 * MS,
+            target: None,
+            next: 0,
+        }
+    }
+
+    // the pointer's somewhere else now (`None` lets go of the jumper, like when the player uses the keys)
+    pub fn point(&mut self, column: Option<usize>) {
+        self.target = column;
+    }
+
+    // which way the jumper should go (if it's not already as close as it can get)
+    fn direction(&self, jumper: &Jumper) -> Option<Key> {
+        let target = self.target? as isize;
+        jumper.heading(target - (jumper.size().0 / 2) as isize)     // (the pointer's for its center)
+    }
+
+    // milliseconds until the next move (if the jumper needs to move at all)
+    pub fn wait_ms(&self, jumper: &Jumper, now: u64) -> Option<c_uint> {
+        self.direction(jumper).map(|_| (self.next.saturating_sub(now) / MS) as c_uint)
+    }
+
+    // the key for the next move (if it's time)
+    pub fn steer(&mut self, jumper: &Jumper, now: u64) -> Option<Key> {
+        match self.direction(jumper) {
+            Some(key) if now >= self.next => {
+                self.next = now + self.rate;
+                Some(key)
+            },
+            _ => None,
+        }
+    }
+}
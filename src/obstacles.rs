@@ -0,0 +1,235 @@
+use config::Obstacles;
+use rand::{Isaac64Rng, Rng};
+use sprites::{Cliff, Jumper};
+
+// Anything that comes up with the cliffs. The game asks for the next bunch whenever there's room for it
+// at the bottom of the box, but it throws them only if the jumper can get past them (so it might ask
+// for a few more before it's happy with one). All of them are numbered by the game when they're thrown.
+pub trait ObstacleGenerator {
+    // the cliffs to be thrown next (all of them starting at the bottom of the box)
+    fn generate(&mut self, jumper: &Jumper, score: usize, rng: &mut Isaac64Rng) -> Vec<Cliff>;
+
+    // the last bunch has been thrown (generators which follow up on the last bunch can move along now)
+    fn accept(&mut self) {}
+}
+
+// (pattern, score from which it shows up, weight)
+const PATTERNS: [(Obstacles, usize, usize); 5] = [
+    (Obstacles::Cliffs, 0, 6),
+    (Obstacles::Ledges, 50, 3),
+    (Obstacles::Boulders, 100, 3),
+    (Obstacles::Walls, 200, 2),
+    (Obstacles::ZigZag, 350, 2),
+];
+const RUN: (usize, usize) = (4, 9);     // bunches thrown before moving on to some other pattern
+const MISSES: usize = 20;   // bunches that the game can turn down before we give up on the pattern
+
+impl Obstacles {
+    pub fn generator(&self) -> Box<dyn ObstacleGenerator> {
+        match *self {
+            Obstacles::Mixed => Box::new(Mixer::new()),
+            Obstacles::Cliffs => Box::new(RandomCliffs),
+            Obstacles::Walls => Box::new(Walls),
+            Obstacles::ZigZag => Box::new(ZigZag::default()),
+            Obstacles::Ledges => Box::new(Ledges::default()),
+            Obstacles::Boulders => Box::new(Boulders),
+        }
+    }
+}
+
+// a full-width wall of the given height, with a gap (where the edges are left out if they're too thin)
+fn wall(jumper: &Jumper, gap_x: usize, gap: usize, height: usize) -> Vec<Cliff> {
+    let width = jumper.area().width.0;
+    let mut wall = vec![];
+    if gap_x >= 3 {
+        wall.push(Cliff::block(0, (gap_x, height), jumper));
+    }
+
+    if gap_x + gap + 3 <= width {
+        wall.push(Cliff::block(gap_x + gap, (width - gap_x - gap, height), jumper));
+    }
+
+    wall
+}
+
+// a gap that's wide enough for the jumper (regardless of where it is, when it's moving in steps)
+fn gap_width(jumper: &Jumper, rng: &mut Isaac64Rng) -> usize {
+    jumper.full_size().0 + jumper.step().0 + rng.gen_range(0, 4)
+}
+
+// the column where a gap of the given width is centered around the given column (within the walls)
+fn gap_around(jumper: &Jumper, center: isize, gap: usize) -> usize {
+    let width = jumper.area().width.0 as isize;
+    (center - gap as isize / 2).max(1).min(width - gap as isize - 1) as usize
+}
+
+// the usual cliffs (one at a time, on either side)
+#[derive(Clone, Debug)]
+pub struct RandomCliffs;
+
+impl ObstacleGenerator for RandomCliffs {
+    fn generate(&mut self, jumper: &Jumper, _score: usize, rng: &mut Isaac64Rng) -> Vec<Cliff> {
+        vec![Cliff::new(0, jumper, rng)]
+    }
+}
+
+// Walls with a gap somewhere around the jumper (it's got some running around to do, but no more than a
+// quarter of the box, since a gap on the other side of a wide box can't be reached in time).
+#[derive(Clone, Debug)]
+pub struct Walls;
+
+impl ObstacleGenerator for Walls {
+    fn generate(&mut self, jumper: &Jumper, _score: usize, rng: &mut Isaac64Rng) -> Vec<Cliff> {
+        let (width, height) = (jumper.area().width.0 as isize, jumper.area().height.0);
+        let gap = gap_width(jumper, rng);
+        let center = jumper.position().0 as isize + jumper.full_size().0 as isize / 2 +
+                     rng.gen_range(-width / 4, width / 4 + 1);
+        wall(jumper, gap_around(jumper, center, gap), gap, rng.gen_range(4, height / 6 + 5))
+    }
+}
+
+// Walls with their gaps drifting the same way, until they hit the side and turn back (so that the jumper
+// has to follow a winding corridor).
+#[derive(Clone, Debug, Default)]
+pub struct ZigZag {
+    gap_x: Option<usize>,   // where the last gap was
+    right: bool,    // which way the gaps are drifting
+    next: (usize, bool),    // (gap, direction) of the last bunch that was asked for
+}
+
+impl ObstacleGenerator for ZigZag {
+    fn generate(&mut self, jumper: &Jumper, _score: usize, rng: &mut Isaac64Rng) -> Vec<Cliff> {
+        let width = jumper.area().width.0;
+        let gap = jumper.full_size().0 + jumper.step().0 + 2;   // (the corridor doesn't change)
+        let drift = rng.gen_range(2, 2 * jumper.step().0 + 4);
+        let (gap_x, right) = match self.gap_x {
+            None => (gap_around(jumper, (jumper.position().0 + jumper.full_size().0 / 2) as isize, gap), self.right),
+            Some(x) if self.right && x + drift + gap < width - 1 => (x + drift, true),
+            Some(x) if !self.right && x > drift => (x - drift, false),
+            Some(x) if self.right => (x.saturating_sub(drift).max(1), false),     // (turning back)
+            Some(x) => ((x + drift).min(width - gap - 1), true),
+        };
+
+        self.next = (gap_x, right);
+        wall(jumper, gap_x, gap, rng.gen_range(4, 6))
+    }
+
+    fn accept(&mut self) {
+        self.gap_x = Some(self.next.0);
+        self.right = self.next.1;
+    }
+}
+
+// Ledges reaching out from the left and right walls, one after the other. They end somewhere around the
+// jumper (anywhere from a quarter to three quarters of the box), so that it always has to make way.
+#[derive(Clone, Debug, Default)]
+pub struct Ledges {
+    right: bool,    // the side of the next ledge
+}
+
+impl ObstacleGenerator for Ledges {
+    fn generate(&mut self, jumper: &Jumper, _score: usize, rng: &mut Isaac64Rng) -> Vec<Cliff> {
+        let width = jumper.area().width.0 as isize;
+        let center = (jumper.position().0 + jumper.full_size().0 / 2) as isize;
+        let end = (center + rng.gen_range(-width / 4, width / 4 + 1)).max(width / 4).min(3 * width / 4) as usize;
+        let height = rng.gen_range(4, 7);
+        match self.right {
+            true => vec![Cliff::block(end, (width as usize - end, height), jumper)],
+            false => vec![Cliff::block(0, (end, height), jumper)],
+        }
+    }
+
+    fn accept(&mut self) {
+        self.right = !self.right;
+    }
+}
+
+// a bunch of small rocks close to each other (with gaps between them, which may or may not be wide enough)
+#[derive(Clone, Debug)]
+pub struct Boulders;
+
+impl ObstacleGenerator for Boulders {
+    fn generate(&mut self, jumper: &Jumper, _score: usize, rng: &mut Isaac64Rng) -> Vec<Cliff> {
+        let width = jumper.area().width.0;
+        let (count, mut x_pos) = (rng.gen_range(2, 6), rng.gen_range(0, width / 2));
+        let mut boulders = vec![];
+        for _ in 0..count {
+            let size = (rng.gen_range(3, 7), rng.gen_range(4, 6));
+            if x_pos + size.0 >= width {
+                break
+            }
+
+            boulders.push(Cliff::block(x_pos, size, jumper));
+            x_pos += size.0 + rng.gen_range(1, jumper.full_size().0 + 4);
+        }
+
+        boulders
+    }
+}
+
+// Goes through all the patterns, sticking to one of them for a few bunches before picking another (by
+// their weights). Only the random cliffs show up at first, and the others join in as the score goes up.
+pub struct Mixer {
+    patterns: Vec<(Box<dyn ObstacleGenerator>, usize, usize)>,     // (generator, score, weight)
+    current: usize,
+    left: usize,    // bunches left before switching to another pattern
+    misses: usize,  // bunches asked for since the last one that was thrown
+}
+
+impl Mixer {
+    pub fn new() -> Mixer {
+        Mixer {
+            patterns: PATTERNS.iter().map(|&(pattern, score, weight)| (pattern.generator(), score, weight)).collect(),
+            current: 0,
+            left: 0,
+            misses: 0,
+        }
+    }
+
+    // picks some other pattern (out of the ones that have shown up), if there's one
+    fn pick(&mut self, score: usize, rng: &mut Isaac64Rng) {
+        let candidates: Vec<(usize, usize)> = self.patterns.iter().enumerate()
+            .filter(|&(i, &(_, from, _))| from <= score && i != self.current)
+            .map(|(i, &(_, _, weight))| (i, weight))
+            .collect();
+        let total = candidates.iter().map(|&(_, weight)| weight).sum::<usize>();
+        self.left = rng.gen_range(RUN.0, RUN.1 + 1);
+        if total == 0 {
+            return      // (stick to the one that we've got)
+        }
+
+        let mut roll = rng.gen_range(0, total);
+        for (i, weight) in candidates {
+            if roll < weight {
+                self.current = i;
+                break
+            }
+
+            roll -= weight;
+        }
+    }
+}
+
+impl Default for Mixer {
+    fn default() -> Mixer {
+        Mixer::new()
+    }
+}
+
+impl ObstacleGenerator for Mixer {
+    fn generate(&mut self, jumper: &Jumper, score: usize, rng: &mut Isaac64Rng) -> Vec<Cliff> {
+        self.misses += 1;
+        if self.left == 0 || self.misses > MISSES {     // (the jumper can't get past this one in here)
+            self.misses = 0;
+            self.pick(score, rng);
+        }
+
+        self.patterns[self.current].0.generate(jumper, score, rng)
+    }
+
+    fn accept(&mut self) {
+        self.left = self.left.saturating_sub(1);
+        self.misses = 0;
+        self.patterns[self.current].0.accept();
+    }
+}
@@ -0,0 +1,179 @@
+use bindings::Bindings;
+use config::{Controls, GameConfig, Obstacles, Profile};
+use frame::Theme;
+use input::Source;
+
+// (flag, setting, description) for the playfield & difficulty parameters
+const SETTINGS: [(&str, &str, &str); 15] = [
+    ("--width", "width", "width of the playfield"),
+    ("--height", "height", "height of the playfield"),
+    ("--timeout", "timeout", "initial time (in ms) between cliff movements"),
+    ("--separation", "separation", "initial lines between consecutive cliffs"),
+    ("--jumper-x", "jumper_x", "columns moved by the jumper for each keypress"),
+    ("--jumper-y", "jumper_y", "rows that the height gets rounded up to a multiple of"),
+    ("--cliff-y", "cliff_y", "rows moved by the cliffs for each tick"),
+    ("--repeat", "repeat", "time (in ms) between moves while a key is held"),
+    ("--acceleration", "acceleration", "how much faster (in %) each of those moves gets"),
+    ("--dive", "dive", "how fast the jumper can dive with the up & down keys"),
+    ("--thrust", "thrust", "momentum gained for each keypress"),
+    ("--drag", "drag", "momentum (in %) lost for each tick"),
+    ("--max-speed", "max_speed", "fastest the jumper can glide sideways"),
+    ("--lives", "lives", "lives for each game (head hits cost one, while limbs cost half)"),
+    ("--tightness", "tightness", "how often (in %) the cliffs need the jumper to move"),
+];
+
+#[derive(Clone, Debug, Default)]
+pub struct Options {    // stuff that can be passed through the command line
+    pub seed: Option<u64>,      // seed for the cliffs (random if not given)
+    pub record: Option<String>,     // file for recording the game
+    pub replay: Option<String>,     // replay file to be played back (instead of a new game)
+    pub profile: Option<String>,    // profile from the config file
+    pub config_file: Option<String>,    // config file (instead of the default one)
+    pub theme: Option<String>,
+    pub controls: Option<String>,   // how the jumper moves sideways
+    pub obstacles: Option<String>,  // what's thrown at the jumper
+    pub keys: Option<String>,       // preset for the key bindings
+    pub mouse: bool,                // steer the jumper with the mouse
+    pub input: Source,              // what moves the jumper
+    pub settings: Vec<(&'static str, usize)>,   // settings given through flags (these override the profile)
+    pub help: bool,
+}
+
+impl Options {
+    pub fn parse<I: Iterator<Item=String>>(mut args: I) -> Result<Options, String> {
+        let mut options = Options::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => {
+                    let value = args.next().ok_or("Missing value for '--seed'!")?;
+                    options.seed = match value.parse() {
+                        Ok(seed) => Some(seed),
+                        Err(_) => return Err(format!("Invalid seed '{}' (expected a positive integer)", value)),
+                    };
+                },
+                "--record" => {
+                    options.record = Some(args.next().ok_or("Missing file for '--record'!")?);
+                },
+                "replay" => {
+                    options.replay = Some(args.next().ok_or("Missing file for 'replay'!")?);
+                },
+                "--profile" => {
+                    options.profile = Some(args.next().ok_or("Missing name for '--profile'!")?);
+                },
+                "--config" => {
+                    options.config_file = Some(args.next().ok_or("Missing file for '--config'!")?);
+                },
+                "--theme" => {
+                    let theme = args.next().ok_or("Missing name for '--theme'!")?;
+                    if Theme::named(&theme).is_none() {
+                        return Err(format!("Unknown theme '{}' (available: {})", theme, Theme::names().join(", ")))
+                    }
+
+                    options.theme = Some(theme);
+                },
+                "--controls" => {
+                    let controls = args.next().ok_or("Missing name for '--controls'!")?;
+                    if Controls::named(&controls).is_none() {
+                        return Err(format!("Unknown controls '{}' (available: {})", controls, Controls::names().join(", ")))
+                    }
+
+                    options.controls = Some(controls);
+                },
+                "--obstacles" => {
+                    let obstacles = args.next().ok_or("Missing name for '--obstacles'!")?;
+                    if Obstacles::named(&obstacles).is_none() {
+                        return Err(format!("Unknown obstacles '{}' (available: {})", obstacles,
+                                           Obstacles::names().join(", ")))
+                    }
+
+                    options.obstacles = Some(obstacles);
+                },
+                "--keys" => {
+                    let preset = args.next().ok_or("Missing preset for '--keys'!")?;
+                    if Bindings::preset(&preset).is_none() {
+                        return Err(format!("Unknown preset '{}' (available: {})", preset, Bindings::presets().join(", ")))
+                    }
+
+                    options.keys = Some(preset);
+                },
+                "--mouse" => options.mouse = true,
+                "--input" => {
+                    options.input = Source::parse(&args.next().ok_or("Missing source for '--input'!")?)?;
+                },
+                "-h" | "--help" => options.help = true,
+                flag => match SETTINGS.iter().find(|&&(setting_flag, _, _)| setting_flag == flag) {
+                    Some(&(_, name, _)) => {
+                        let value = args.next().ok_or_else(|| format!("Missing value for '{}'!", flag))?;
+                        let value = value.parse().map_err(|_| {
+                            format!("Invalid value '{}' for '{}' (expected a positive integer)", value, flag)
+                        })?;
+                        GameConfig::default().set(name, value).map_err(|err| format!("Invalid '{}': {}", flag, err))?;
+                        options.settings.push((name, value));
+                    },
+                    None => return Err(format!("Unknown option '{}' (try '--help')", arg)),
+                },
+            }
+        }
+
+        Ok(options)
+    }
+
+    // loads the profile (from the config file) and applies the settings from the command line over it
+    pub fn load_profile(&self) -> Result<Profile, String> {
+        let name = self.profile.as_deref();
+        let mut profile = match self.config_file {
+            Some(ref path) => Profile::from_file(path, name)?,
+            None => Profile::load(name)?,
+        };
+
+        for &(name, value) in &self.settings {
+            profile.config.set(name, value)?;
+        }
+
+        if let Some(theme) = self.theme.as_ref().and_then(|theme| Theme::named(theme)) {
+            profile.config.theme = theme;
+        }
+
+        if let Some(controls) = self.controls.as_ref().and_then(|controls| Controls::named(controls)) {
+            profile.config.controls = controls;
+        }
+
+        if let Some(obstacles) = self.obstacles.as_ref().and_then(|obstacles| Obstacles::named(obstacles)) {
+            profile.config.obstacles = obstacles;
+        }
+
+        if let Some(bindings) = self.keys.as_ref().and_then(|preset| Bindings::preset(preset)) {
+            profile.bindings = bindings;
+        }
+
+        profile.mouse |= self.mouse;
+        profile.config.validate()?;
+        Ok(profile)
+    }
+
+    pub fn usage() -> String {
+        let mut usage = "Usage: free-fall [OPTIONS]\n       free-fall replay <FILE>\n\nOptions:\n".to_owned();
+        usage.push_str("    --seed <N>           seed for the cliffs (random by default)\n");
+        usage.push_str("    --record <FILE>      record the game into a replay file\n");
+        usage.push_str("    --profile <NAME>     use a profile from the config file\n");
+        usage.push_str("    --config <FILE>      config file (default: ~/.config/free-fall/config.toml)\n");
+        usage.push_str(&format!("    --theme <NAME>       color theme ({})\n", Theme::names().join(", ")));
+        usage.push_str(&format!("    --controls <NAME>    how the jumper moves sideways ({}, default: steps)\n",
+                                Controls::names().join(", ")));
+        usage.push_str(&format!("    --obstacles <NAME>   what's thrown at the jumper ({}, default: mixed)\n",
+                                Obstacles::names().join(", ")));
+        let defaults = GameConfig::default();
+        for &(flag, name, description) in &SETTINGS {
+            let (min, max) = GameConfig::limits(name).unwrap_or((0, 0));
+            let flag = format!("{} <N>", flag);
+            usage.push_str(&format!("    {:<20} {} ({}-{}, default: {})\n",
+                                    flag, description, min, max, defaults.get(name).unwrap_or(0)));
+        }
+
+        usage.push_str(&format!("    --keys <PRESET>      key bindings ({}, default: arrows)\n", Bindings::presets().join(", ")));
+        usage.push_str("    --mouse              steer the jumper with the mouse pointer\n");
+        usage.push_str("    --input <SOURCE>     what moves the jumper (terminal, bot, replay:FILE, script:MOVES)\n");
+        usage.push_str("    -h, --help           show this message\n");
+        usage
+    }
+}
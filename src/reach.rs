@@ -0,0 +1,213 @@
+use config::{Controls, GameConfig};
+use sprites::{Cliff, Jumper, SUBCELLS};
+use std::collections::HashSet;
+
+const SPEEDS: isize = 10;   // speeds that are this close are taken as the same (so that there aren't too many)
+const MAX_TICKS: usize = 1000;  // (for jumpers that are taking their time)
+
+// Plays the game ahead of time (without the RNG) to find where the jumper could be as the cliffs come up,
+// so that the game never throws a bunch of cliffs that can't be dodged. The jumper's assumed to press a
+// key only on some of the ticks (as given by the tightness) and once at most, and to keep falling as fast
+// as it is (or at its usual pace, if it's braking), since a layout which needs a perfect player isn't much
+// fun either. Every way that's tried is one that the player can actually take (moving in steps, or pushing
+// the jumper around with its momentum), so if one of them gets past the cliffs, then there's a way past them.
+#[derive(Clone, Debug)]
+pub struct Reach {
+    jumper: Jumper,     // (as it is right now)
+    start: Vec<(isize, isize)>,     // (position, velocity) where the jumper could be right now
+    pending: usize,     // shifts left in the current tick
+    first: Option<isize>,   // push (-1, 0 or 1) that all the ways should start with
+    controls: Controls,
+    rise: usize,        // rows moved by the cliffs for every shift
+    tightness: usize,   // how often (in %) the jumper presses a key
+}
+
+impl Reach {
+    pub fn new(jumper: &Jumper, config: &GameConfig, pending: usize) -> Reach {
+        let start = match config.controls {
+            Controls::Steps => (jumper.position().0 as isize * SUBCELLS, 0),
+            Controls::Momentum => jumper.momentum(),
+        };
+
+        Reach {
+            jumper: jumper.clone(),
+            start: vec![start],
+            pending,
+            first: None,
+            controls: config.controls,
+            rise: config.cliff_y,
+            tightness: config.tightness,
+        }
+    }
+
+    // only the ways that start with the given push (left, none or right), for picking the next key
+    pub fn starting_with(mut self, push: isize) -> Reach {
+        self.first = Some(push);
+        self
+    }
+
+    // counting on a keypress on every tick (like the bot does)
+    pub fn every_tick(mut self) -> Reach {
+        self.tightness = 100;
+        self
+    }
+
+    // judge the cliffs as if the jumper could be anywhere (for when it's in trouble already)
+    pub fn anywhere(mut self) -> Reach {
+        let width = self.jumper.area().width.0 - self.jumper.full_size().0;
+        self.start = (1..width - 1).map(|col| (col as isize * SUBCELLS, 0)).collect();
+        self
+    }
+
+    // the columns where the jumper (at its full size) isn't touching any of the cliffs or the walls, when
+    // its top is at the given row and the cliffs have risen by the given rows (along with the rows that
+    // they've just swept past the jumper, on their way there)
+    fn open(&self, cliffs: &[&Cliff], row: usize, risen: usize, swept: usize) -> Vec<bool> {
+        let (width, size) = (self.jumper.area().width.0, self.jumper.full_size());
+        let mut open: Vec<bool> = (0..width).map(|col| col >= 1 && col + size.0 < width).collect();
+        for cliff in cliffs {
+            let ((cliff_x, cliff_y), (cliff_width, cliff_height)) = (cliff.position(), cliff.size());
+            let top = cliff_y as isize - risen as isize;
+            if top < (row + size.1) as isize && top + (cliff_height + swept) as isize > row as isize {
+                let left = (cliff_x + 1).saturating_sub(size.0);
+                for col in open.iter_mut().take(cliff_x + cliff_width).skip(left) {
+                    *col = false;
+                }
+            }
+        }
+
+        open
+    }
+
+    // Whether there's a way past all the cliffs, ticking along until the last one's above the jumper. The
+    // collisions are checked for every row that the cliffs rise past the jumper in a tick (and after every
+    // keypress), just like the game.
+    pub fn clears(&self, cliffs: &[&Cliff]) -> bool {
+        // the jumper's top row and how far the cliffs have risen, at the end of every tick from now on
+        let mut ticks = vec![(self.jumper.position().1, self.pending * self.rise)];
+        let mut falls = self.jumper.falls(self.rise);
+        let passing = |&(row, risen): &(usize, usize)| cliffs.iter().any(|cliff| {
+            cliff.position().1 + cliff.size().1 > row + risen
+        });
+
+        while ticks.len() < MAX_TICKS && passing(&ticks[ticks.len() - 1]) {
+            let (row, shifts) = falls.next().unwrap_or((0, 0));
+            let risen = ticks[ticks.len() - 1].1 + shifts * self.rise;
+            ticks.push((row, risen));
+        }
+
+        let column = |x: isize| ((x + SUBCELLS / 2) / SUBCELLS) as usize;
+        let clear = |open: &[bool], x: isize| x >= 0 && open.get(column(x)).cloned().unwrap_or(false);
+        let step = self.jumper.step().0 as isize * SUBCELLS;
+        // (the ones for the keypresses are the columns that are open once the tick's over)
+        let mut open = self.open(cliffs, ticks[0].0, ticks[0].1, 0);
+        let now_open = self.open(cliffs, ticks[0].0, ticks[0].1, ticks[0].1);    // (the rest of this tick)
+        let mut states: Vec<(isize, isize)> = self.start.iter().cloned().filter(|&(x, _)| clear(&now_open, x)).collect();
+        for (tick, &(row, risen)) in ticks.iter().enumerate().skip(1) {
+            let moving = tick * self.tightness / 100 > (tick - 1) * self.tightness / 100;
+            let (last_row, last_risen) = ticks[tick - 1];
+            let swept = (risen - last_risen + row) as isize - last_row as isize;
+            let next_open = self.open(cliffs, row, risen, swept.max(0) as usize);
+            let (mut seen, mut next) = (HashSet::new(), vec![]);
+            for &(x, velocity) in &states {
+                let allowed = |push: isize| match self.first {
+                    Some(first) if tick == 1 => push == first,
+                    _ => moving || push == 0,
+                };
+
+                for &push in [0, -1, 1].iter().filter(|&&push| allowed(push)) {
+                    let (x, velocity) = match self.controls {
+                        // a step moves the jumper right away (so it shouldn't run into anything right there)
+                        Controls::Steps if clear(&open, x + push * step) => (x + push * step, 0),
+                        Controls::Steps => continue,
+                        Controls::Momentum => self.jumper.coast((x, velocity), push),
+                    };
+
+                    if clear(&next_open, x) && seen.insert((column(x), velocity / SPEEDS)) {
+                        next.push((x, velocity));
+                    }
+                }
+            }
+
+            if next.is_empty() {
+                return false
+            }
+
+            states = next;
+            open = self.open(cliffs, row, risen, 0);
+        }
+
+        !states.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Reach;
+    use config::{Controls, GameConfig};
+    use helpers::FallArea;
+    use sprites::{Cliff, Jumper};
+
+    fn jumper(config: &GameConfig) -> Jumper {
+        Jumper::new(FallArea::fit(config, 40, 80).unwrap(), config)
+    }
+
+    // a full-width wall (at the bottom of the box) with a gap at the given column (if any)
+    fn wall(jumper: &Jumper, gap: Option<(usize, usize)>) -> Vec<Cliff> {
+        let width = jumper.area().width.0;
+        match gap {
+            Some((gap_x, gap)) => vec![Cliff::block(0, (gap_x, 4), jumper),
+                                       Cliff::block(gap_x + gap, (width - gap_x - gap, 4), jumper)],
+            None => vec![Cliff::block(0, (width, 4), jumper)],
+        }
+    }
+
+    fn clears(reach: Reach, cliffs: &[Cliff]) -> bool {
+        reach.clears(&cliffs.iter().collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn blocked_wall() {
+        for &controls in &[Controls::Steps, Controls::Momentum] {
+            let config = GameConfig { controls, tightness: 100, ..GameConfig::default() };
+            let jumper = jumper(&config);
+            let cliffs = wall(&jumper, None);
+            assert!(!clears(Reach::new(&jumper, &config, 0), &cliffs));
+            assert!(!clears(Reach::new(&jumper, &config, 0).anywhere(), &cliffs));
+        }
+    }
+
+    #[test]
+    fn reachable_gap() {
+        for &controls in &[Controls::Steps, Controls::Momentum] {
+            let config = GameConfig { controls, ..GameConfig::default() };
+            let jumper = jumper(&config);
+            let gap = jumper.full_size().0 + jumper.step().0 + 2;
+            // (right below the jumper, and a few steps away)
+            for &gap_x in &[jumper.position().0 - 2, 8] {
+                assert!(clears(Reach::new(&jumper, &config, 0), &wall(&jumper, Some((gap_x, gap)))),
+                        "{:?} gap at {}", controls, gap_x);
+            }
+
+            // (too narrow for the jumper)
+            let cliffs = wall(&jumper, Some((jumper.position().0, jumper.full_size().0 - 1)));
+            assert!(!clears(Reach::new(&jumper, &config, 0), &cliffs));
+        }
+    }
+
+    #[test]
+    fn tightness() {
+        for &controls in &[Controls::Steps, Controls::Momentum] {
+            let lazy = GameConfig { controls, tightness: 10, ..GameConfig::default() };
+            let jumper = jumper(&lazy);
+            let gap = jumper.full_size().0 + jumper.step().0 + 2;
+            let (near, far) = (wall(&jumper, Some((jumper.position().0 - 2, gap))), wall(&jumper, Some((2, gap))));
+
+            // a gap right below is fine for anyone, but getting across the box takes a lot of keypresses
+            assert!(clears(Reach::new(&jumper, &lazy, 0), &near));
+            assert!(!clears(Reach::new(&jumper, &lazy, 0), &far), "{:?}", controls);
+            assert!(clears(Reach::new(&jumper, &lazy, 0).every_tick(), &far), "{:?}", controls);
+            assert!(clears(Reach::new(&jumper, &lazy, 0).anywhere(), &far), "{:?}", controls);
+        }
+    }
+}
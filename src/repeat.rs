@@ -0,0 +1,121 @@
+use config::GameConfig;
+use helpers::MS;
+use keyevents::{Key, KeyKind};
+use libc::c_uint;
+
+const HOLD_DELAY_NS: u64 = 200 * MS;    // so that a single press moves the jumper only once
+const RELEASE_NS: u64 = 120 * MS;       // terminals repeat held keys faster than this
+
+#[derive(Clone, Debug)]
+struct Held {
+    key: Key,
+    repeating: bool,    // whether the key is actually being held (and not just pressed)
+    last_seen: u64,     // last time the terminal told us about this key
+    next: u64,          // time for the next move
+    interval: u64,      // time between moves (which shrinks when there's acceleration)
+}
+
+// Keeps the jumper moving while a key is held down. Terminals supporting the kitty keyboard protocol tell
+// us when a key is released, but for others, we assume that the key is held for as long as the terminal
+// keeps repeating it. Either way, the moves happen at our own rate (and not the terminal's).
+#[derive(Clone, Debug)]
+pub struct KeyRepeat {
+    rate: u64,
+    acceleration: u64,      // percentage by which the interval shrinks after every move
+    releases: bool,         // whether the terminal reports releases (we know that once we see one)
+    held: Option<Held>,
+}
+
+impl KeyRepeat {
+    pub fn new(config: &GameConfig) -> KeyRepeat {
+        KeyRepeat {
+            rate: config.repeat as u64 * MS,
+            acceleration: config.acceleration as u64,
+            releases: false,
+            held: None,
+        }
+    }
+
+    // returns whether the key should move the jumper right away
+    pub fn press(&mut self, key: Key, kind: KeyKind, now: u64) -> bool {
+        let (rate, releases) = (self.rate, self.releases);
+        match (kind, self.held.as_mut()) {
+            (KeyKind::Release, held) => {
+                self.releases = true;
+                if held.is_some_and(|held| held.key == key) {
+                    self.held = None;
+                }
+
+                false
+            },
+            (_, Some(ref mut held)) if held.key == key && releases => false,    // it's held until it's released
+            (KeyKind::Repeat, None) if releases => false,
+            (_, Some(ref mut held)) if held.key == key => {
+                let quick = now - held.last_seen < RELEASE_NS;
+                held.last_seen = now;
+                match (held.repeating, quick) {
+                    (false, true) => {      // the terminal has started repeating it, so we take over from here
+                        held.repeating = true;
+                        held.next = now + rate;
+                        held.interval = rate;
+                        true
+                    },
+                    (true, true) => false,
+                    (_, false) => {     // pressed again
+                        held.repeating = false;
+                        true
+                    },
+                }
+            },
+            _ => {
+                self.held = Some(Held {
+                    key,
+                    repeating: releases,
+                    last_seen: now,
+                    next: now + HOLD_DELAY_NS.max(rate),
+                    interval: rate,
+                });
+
+                true
+            },
+        }
+    }
+
+    // milliseconds until the next move (if there's a key being held)
+    pub fn wait_ms(&self, now: u64) -> Option<c_uint> {
+        match self.held {
+            Some(ref held) if held.repeating => Some((held.next.saturating_sub(now) / MS) as c_uint),
+            _ => None,
+        }
+    }
+
+    // the key for the next move (if it's time)
+    pub fn repeat(&mut self, now: u64) -> Option<Key> {
+        let (releases, acceleration, rate) = (self.releases, self.acceleration, self.rate);
+        let released = match self.held {
+            Some(ref mut held) if held.repeating => {
+                if !releases && now - held.last_seen > RELEASE_NS {
+                    true    // the terminal has stopped repeating, so it must've been released
+                } else if now >= held.next {
+                    held.interval = (held.interval * (100 - acceleration) / 100).max(rate / 4);
+                    held.next = now + held.interval;
+                    return Some(held.key)
+                } else {
+                    false
+                }
+            },
+            _ => false,
+        };
+
+        if released {
+            self.held = None;
+        }
+
+        None
+    }
+
+    // forget about the held key (it can't be tracked while the game's paused)
+    pub fn clear(&mut self) {
+        self.held = None;
+    }
+}
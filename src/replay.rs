@@ -0,0 +1,251 @@
+use config::{Controls, GameConfig, Obstacles};
+use keyevents::Key;
+use sprites::Input;
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+const HEADER: &str = "free-fall replay";
+// bumped whenever the same seed & keys play out differently (older replays can't be played back anymore)
+const VERSION: usize = 4;
+
+// Replays are plain text files with a header (seed & game settings), followed by the keys fed to the jumper,
+// each stamped with the tick (number of cliff shifts) at which it was pressed. Something like...
+//
+//      free-fall replay 4
+//      seed 1234
+//      width 50
+//      ...
+//      controls steps
+//      obstacles mixed
+//      ticks 96
+//      12 L
+//      15 R
+//
+// Settings missing from the header take their default values.
+// Since the cliffs depend only on the seed, feeding the same keys at the same ticks gives back the same game.
+#[derive(Clone, Debug)]
+pub struct Replay {
+    pub seed: u64,
+    pub config: GameConfig,
+    pub ticks: usize,       // total ticks in the recording (so that we know when it ends)
+    pub events: Vec<(usize, Key)>,      // (tick, key)
+}
+
+fn key_code(key: Key) -> Option<char> {     // only the keys that move the jumper are worth recording
+    match key {
+        Key::Up => Some('U'),
+        Key::Down => Some('D'),
+        Key::Right => Some('R'),
+        Key::Left => Some('L'),
+        _ => None,
+    }
+}
+
+fn code_key(code: &str) -> Option<Key> {
+    match code {
+        "U" => Some(Key::Up),
+        "D" => Some(Key::Down),
+        "R" => Some(Key::Right),
+        "L" => Some(Key::Left),
+        _ => None,
+    }
+}
+
+impl Replay {
+    pub fn new(seed: u64, config: GameConfig) -> Replay {
+        Replay {
+            seed,
+            config,
+            ticks: 0,
+            events: vec![],
+        }
+    }
+
+    // record an input that's about to be fed to the game at the given tick
+    pub fn record(&mut self, tick: usize, input: Input) {
+        match input {
+            Input::Key(key) if key_code(key).is_some() => self.events.push((tick, key)),
+            Input::Tick => self.ticks = tick + 1,
+            _ => (),
+        }
+    }
+
+    // all the inputs (in the same order they were fed to the game)
+    pub fn inputs(&self) -> Vec<Input> {
+        let mut inputs = Vec::with_capacity(self.events.len() + self.ticks);
+        let mut events = self.events.iter().peekable();
+        for tick in 0..(self.ticks + 1) {
+            while let Some(&&(_, key)) = events.peek().filter(|&&&(t, _)| t == tick) {
+                inputs.push(Input::Key(key));
+                events.next();
+            }
+
+            if tick < self.ticks {
+                inputs.push(Input::Tick);
+            }
+        }
+
+        inputs
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let mut contents = format!("{} {}\nseed {}\n", HEADER, VERSION, self.seed);
+        for name in GameConfig::names() {
+            contents.push_str(&format!("{} {}\n", name, self.config.get(name).unwrap_or(0)));
+        }
+
+        contents.push_str(&format!("controls {}\nobstacles {}\nticks {}\n", self.config.controls.name(),
+                                   self.config.obstacles.name(), self.ticks));
+        for &(tick, key) in &self.events {
+            if let Some(code) = key_code(key) {
+                contents.push_str(&format!("{} {}\n", tick, code));
+            }
+        }
+
+        File::create(path)
+             .and_then(|mut file| file.write_all(contents.as_bytes()))
+             .map_err(|err| format!("Can't save the replay to {}: {}", path.display(), err))
+    }
+
+    pub fn load(path: &Path) -> Result<Replay, String> {
+        let file = File::open(path).map_err(|err| format!("Can't open {}: {}", path.display(), err))?;
+        let mut lines = BufReader::new(file).lines();
+        let version = match lines.next() {
+            Some(Ok(ref header)) if header.trim().starts_with(HEADER) => header.trim()[HEADER.len()..].trim().parse().ok(),
+            _ => None,
+        };
+
+        match version {
+            Some(VERSION) => (),
+            Some(version) => return Err(format!("{} was recorded by another version of the game (replay format {}, \
+                                                 but this one plays format {})", path.display(), version, VERSION)),
+            None => return Err(format!("{} is not a replay file!", path.display())),
+        }
+
+        let (mut seed, mut ticks) = (None, None);
+        let mut replay = Replay::new(0, GameConfig::default());
+        for (num, line) in lines.enumerate() {
+            let num = num + 2;      // we've already read the first line
+            let line = line.map_err(|err| format!("Can't read line {} of {}: {}", num, path.display(), err))?;
+            let mut words = line.split_whitespace();
+            let (name, value) = match (words.next(), words.next()) {
+                (Some(name), Some(value)) => (name, value),
+                (None, _) => continue,      // blank lines are fine
+                _ => return Err(format!("Invalid line {} in {}", num, path.display())),
+            };
+
+            let invalid = |what: &str| format!("Invalid {} in line {} of {}", what, num, path.display());
+            match (name, ticks) {
+                ("seed", None) => seed = Some(value.parse().map_err(|_| invalid("seed"))?),
+                ("ticks", None) => ticks = Some(value.parse().map_err(|_| invalid("tick count"))?),
+                ("controls", None) => {
+                    replay.config.controls = Controls::named(value).ok_or_else(|| invalid("controls"))?;
+                },
+                ("obstacles", None) => {
+                    replay.config.obstacles = Obstacles::named(value).ok_or_else(|| invalid("obstacles"))?;
+                },
+                (_, None) => {
+                    let value = value.parse().map_err(|_| invalid(name))?;
+                    replay.config.set(name, value).map_err(|err| invalid(&err))?;
+                },
+                (_, Some(total)) => {   // events come after the header
+                    let event = match (name.parse(), code_key(value)) {
+                        (Ok(tick), Some(key)) if tick <= total => (tick, key),
+                        _ => return Err(invalid("event")),
+                    };
+
+                    match replay.events.last() {
+                        Some(&(last_tick, _)) if last_tick > event.0 =>
+                            return Err(format!("Event in line {} of {} goes back in time!", num, path.display())),
+                        _ => replay.events.push(event),
+                    }
+                },
+            }
+        }
+
+        match (seed, ticks) {
+            (Some(seed), Some(ticks)) => {
+                replay.config.validate().map_err(|err| format!("Invalid settings in {}: {}", path.display(), err))?;
+                replay.seed = seed;
+                replay.ticks = ticks;
+                Ok(replay)
+            },
+            _ => Err(format!("{} doesn't have a seed or tick count!", path.display())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Replay, HEADER, VERSION};
+    use config::{Controls, GameConfig, Obstacles};
+    use keyevents::Key;
+    use sprites::Input;
+
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::Write;
+    use std::path::PathBuf;
+    use std::process;
+
+    fn temp_path(name: &str) -> PathBuf {   // (unique for every test run, so that they don't trip over each other)
+        env::temp_dir().join(format!("free-fall-{}-{}", process::id(), name))
+    }
+
+    fn load_text(name: &str, text: &str) -> Result<Replay, String> {
+        let path = temp_path(name);
+        File::create(&path).and_then(|mut file| file.write_all(text.as_bytes())).unwrap();
+        let replay = Replay::load(&path);
+        fs::remove_file(&path).unwrap();
+        replay
+    }
+
+    #[test]
+    fn save_and_load_give_back_the_same_inputs() {
+        let mut config = GameConfig { controls: Controls::Steps, obstacles: Obstacles::ZigZag, ..GameConfig::default() };
+        config.set("cliff_y", 3).unwrap();
+
+        let mut replay = Replay::new(1234, config);
+        let fed = vec![Input::Key(Key::Left), Input::Tick, Input::Tick, Input::Key(Key::Up), Input::Key(Key::Right),
+                       Input::Tick, Input::Key(Key::Down)];
+        let mut tick = 0;
+        for &input in &fed {
+            replay.record(tick, input);
+            if input == Input::Tick {
+                tick += 1;
+            }
+        }
+
+        replay.record(tick, Input::Key(Key::Char('p')));    // (not something that moves the jumper)
+        assert_eq!(replay.inputs(), fed);
+
+        let path = temp_path("round-trip");
+        replay.save(&path).unwrap();
+        let loaded = Replay::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.seed, 1234);
+        assert_eq!(loaded.config, config);
+        assert_eq!(loaded.ticks, 3);
+        assert_eq!(loaded.events, replay.events);
+        assert_eq!(loaded.inputs(), fed);
+    }
+
+    #[test]
+    fn bad_replays() {
+        let header = format!("{} {}\nseed 1\nticks 5\n", HEADER, VERSION);
+        assert!(load_text("ok", &format!("{}2 L\n2 R\n5 U\n", header)).is_ok());
+
+        let old = load_text("old", &format!("{} {}\nseed 1\nticks 5\n", HEADER, VERSION - 1)).unwrap_err();
+        assert!(old.contains("another version"), "{}", old);
+        assert!(load_text("junk", "seed 1\nticks 5\n").unwrap_err().contains("not a replay file"));
+        assert!(load_text("no-seed", &format!("{} {}\nticks 5\n", HEADER, VERSION)).is_err());
+        assert!(load_text("backwards", &format!("{}3 L\n2 R\n", header)).unwrap_err().contains("back in time"));
+        assert!(load_text("late", &format!("{}6 L\n", header)).is_err());
+        assert!(load_text("key", &format!("{}2 X\n", header)).is_err());
+        assert!(load_text("setting", &format!("{} {}\nseed 1\nwidth 3\nticks 5\n", HEADER, VERSION)).is_err());
+    }
+}
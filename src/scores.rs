@@ -0,0 +1,110 @@
+use libc::c_uint;
+
+use std::cmp::Reverse;
+use std::env;
+use std::fs::{self, File};
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+
+pub const MAX_SCORES: usize = 10;       // only the top ones make it to the table
+pub const NAME_LENGTH: usize = 3;          // initials, like the good old arcade machines
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Score {
+    pub name: String,
+    pub score: usize,
+    pub max_speed: c_uint,
+    pub seed: u64,
+    pub date: String,
+    pub cause: String,      // collision message that ended the game
+}
+
+impl Score {
+    // scores are stored as tab-separated lines (cause goes at the end, since it has spaces in it)
+    fn to_line(&self) -> String {
+        format!("{}\t{}\t{}\t{}\t{}\t{}", self.score, self.max_speed, self.seed, self.date, self.name, self.cause)
+    }
+
+    fn from_line(line: &str) -> Option<Score> {
+        let mut fields = line.splitn(6, '\t');
+        let mut next = || fields.next().map(|field| field.trim());
+        Some(Score {
+            score: next()?.parse().ok()?,
+            max_speed: next()?.parse().ok()?,
+            seed: next()?.parse().ok()?,
+            date: next()?.to_owned(),
+            name: next()?.to_owned(),
+            cause: next()?.to_owned(),
+        })
+    }
+}
+
+pub struct HighScores {     // top-N table, which lives in the user's data directory
+    path: PathBuf,
+    entries: Vec<Score>,    // sorted in descending order of scores
+}
+
+impl HighScores {
+    // `$XDG_DATA_HOME/free-fall/scores` (or `~/.local/share/free-fall/scores` if that's not set)
+    pub fn default_path() -> Option<PathBuf> {
+        let data_dir = match env::var_os("XDG_DATA_HOME") {
+            Some(ref dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(env::var_os("HOME")?).join(".local").join("share"),
+        };
+
+        Some(data_dir.join("free-fall").join("scores"))
+    }
+
+    pub fn load(path: &Path) -> Result<HighScores, String> {
+        let mut contents = String::new();
+        match File::open(path).and_then(|mut file| file.read_to_string(&mut contents)) {
+            Ok(_) => (),
+            Err(ref err) if err.kind() == ErrorKind::NotFound => (),    // nobody's played yet!
+            Err(err) => return Err(format!("Can't read the high scores from {}: {}", path.display(), err)),
+        }
+
+        let mut entries = contents.lines().filter_map(Score::from_line).collect::<Vec<_>>();
+        entries.sort_by_key(|entry| Reverse(entry.score));
+        entries.truncate(MAX_SCORES);
+        Ok(HighScores {
+            path: path.to_owned(),
+            entries,
+        })
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let contents = self.entries.iter().map(|entry| entry.to_line() + "\n").collect::<String>();
+        self.path.parent().map_or(Ok(()), fs::create_dir_all)
+                 .and_then(|_| File::create(&self.path))
+                 .and_then(|mut file| file.write_all(contents.as_bytes()))
+                 .map_err(|err| format!("Can't save the high scores to {}: {}", self.path.display(), err))
+    }
+
+    pub fn entries(&self) -> &[Score] {
+        &self.entries
+    }
+
+    pub fn best(&self) -> Option<&Score> {
+        self.entries.first()
+    }
+
+    pub fn qualifies(&self, score: usize) -> bool {     // will this score make it to the table?
+        score > 0 && (self.entries.len() < MAX_SCORES || self.entries.iter().any(|entry| entry.score < score))
+    }
+
+    // adds the score to the table and returns its rank (if it made it)
+    pub fn insert(&mut self, mut score: Score) -> Option<usize> {
+        if !self.qualifies(score.score) {
+            return None
+        }
+
+        score.name = score.name.chars()
+                               .filter(|c| !c.is_control())
+                               .take(NAME_LENGTH)
+                               .collect();
+        let rank = self.entries.iter().position(|entry| entry.score < score.score).unwrap_or(self.entries.len());
+        self.entries.insert(rank, score);
+        self.entries.truncate(MAX_SCORES);
+        Some(rank)
+    }
+}
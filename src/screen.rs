@@ -0,0 +1,215 @@
+use frame::{Cell, Frame};
+use helpers::*;
+use sprites::{FrameState, HEALTH_PER_LIFE};
+
+use std::fmt::Write as FmtWrite;
+use std::io::{self, Write};
+
+// Double-buffered terminal front-end which draws the frames inside an outlined box. It remembers the last
+// frame it drew, and moves the cursor around to overwrite only the characters that have changed since then.
+pub struct Screen {
+    origin: (usize, usize),     // (row, column) of the box's top left corner (both start from 1 in terminals)
+    size: (usize, usize),       // (width, height) of the frames drawn inside the box
+    rows: usize,                // terminal's height
+    last: Option<Frame>,        // previous frame (nothing when the screen needs to be redrawn)
+    lines: Vec<String>,         // status lines shown below the box (HUD, messages, etc.)
+    cursor: Option<(usize, usize)>,     // where the cursor is right now (if we know that)
+    best: Option<usize>,        // high score (shown in the HUD)
+    notice: Option<String>,     // something that's gone wrong (shown in the status line whenever the box is redrawn)
+}
+
+impl Screen {
+    pub fn new(fall_area: FallArea) -> Screen {
+        let mut screen = Screen {
+            origin: (1, 1),
+            size: (fall_area.width.0, fall_area.height.0),
+            rows: 0,
+            last: None,
+            lines: vec![],
+            cursor: None,
+            best: None,
+            notice: None,
+        };
+
+        screen.resize(fall_area);
+        screen
+    }
+
+    // next frame will be drawn from scratch (box and all, but the status lines are gone)
+    pub fn redraw(&mut self) {
+        self.last = None;
+        self.lines.clear();
+        self.cursor = None;
+    }
+
+    // move the box around for the (resized) area, which may also be some other area altogether
+    pub fn resize(&mut self, fall_area: FallArea) {
+        self.size = (fall_area.width.0, fall_area.height.0);
+        // (centered in whatever's left of the window, once the borders and the lines below have their room)
+        self.origin = (fall_area.height.1.saturating_sub(BORDERS + LINES_BELOW) / 2 + 1,
+                       fall_area.width.1.saturating_sub(BORDERS) / 2 + 1);
+        self.rows = fall_area.height.0 + fall_area.height.1;
+        self.redraw();
+    }
+
+    // column inside the box for the given terminal column (stuff outside the box goes to its nearest edge)
+    pub fn column(&self, col: usize) -> usize {
+        col.saturating_sub(self.origin.1 + 1).min(self.size.0 - 1)
+    }
+
+    pub fn show_best(&mut self, best: Option<usize>) {
+        self.best = best;
+    }
+
+    pub fn show_notice(&mut self, notice: &str) {
+        self.notice = Some(notice.to_owned());
+    }
+
+    // shown (instead of the game) when the terminal can't fit the box
+    pub fn print_too_small(&mut self, rows: usize, cols: usize) {
+        let msg = format!("Please enlarge the terminal to at least {} rows and {} columns (it's {}x{} now)",
+                          self.size.1 + BORDERS + LINES_BELOW, self.size.0 + BORDERS, rows, cols);
+        Screen::flush(&format!("\x1B[2J\x1B[1;1H{}", colored(&msg, Some("Y"))));
+        self.redraw();
+    }
+
+    fn move_to(&mut self, out: &mut String, row: usize, col: usize) {
+        if self.cursor != Some((row, col)) {
+            let _ = write!(out, "\x1B[{};{}H", row, col);
+        }
+    }
+
+    fn put(&mut self, out: &mut String, row: usize, col: usize, string: &str) {
+        self.move_to(out, row, col);
+        out.push_str(string);
+        self.cursor = Some((row, col + string.chars().count()));
+    }
+
+    fn draw_box(&mut self, out: &mut String) {
+        let (row, col) = self.origin;
+        let (width, height) = self.size;
+        let dashes = multiply("-", width + 2);
+        out.push_str("\x1B[2J");    // clear everything
+        self.put(out, row, col, &dashes);
+        for i in 1..(height + 1) {
+            self.put(out, row + i, col, "|");
+            self.put(out, row + i, col + width + 1, "|");
+        }
+
+        self.put(out, row + height + 1, col, &dashes);
+    }
+
+    fn flush(out: &str) {
+        print!("{}", out);
+        let _ = io::stdout().flush();
+    }
+
+    pub fn print_frame(&mut self, state: &FrameState) {     // gameplay inside an outlined box
+        self.draw(&state.frame);
+        let mut out = String::new();
+        let mut hud = format!("SCORE: {}\tSPEED: {}", state.score, state.speed);
+        let (health, full) = state.health;
+        if full > HEALTH_PER_LIFE || health < full {    // (nothing to show for a single life that's untouched)
+            let bar = (0..full).map(|point| if point < health { '#' } else { '-' }).collect::<String>();
+            hud.push_str(&format!("\tLIVES: {} [{}]", health.div_ceil(HEALTH_PER_LIFE), bar));
+        }
+
+        if let Some(best) = self.best {
+            hud.push_str(&format!("\tBEST: {}", best.max(state.score)));
+        }
+
+        for &(power, left) in &state.powers {   // (along with the ticks they have left)
+            hud.push_str(&format!("\t{}: {}", power.name(), left));
+        }
+
+        let hud = colored(&hud, Some("G"));
+        self.print_line(&mut out, 0, &hud);
+        Screen::flush(&out);
+    }
+
+    // draws whatever's changed in the frame (inside the box)
+    pub fn draw(&mut self, frame: &Frame) {
+        let mut out = String::new();
+        if self.last.is_none() {
+            self.draw_box(&mut out);
+            if let Some(notice) = self.notice.clone() {
+                self.print_line(&mut out, 1, &colored(&notice, Some("Y")));
+            }
+        }
+
+        let (row, col) = (self.origin.0 + 1, self.origin.1 + 1);
+        let mut pen: Option<Cell> = None;   // style of the last cell we've drawn
+        for (y, cells) in frame.rows().enumerate() {
+            for (x, cell) in cells.iter().enumerate() {
+                if self.last.as_ref().and_then(|old| old.get(x, y)) == Some(cell) {
+                    continue
+                }
+
+                if !pen.map(|pen| pen.same_style(cell)).unwrap_or(false) {
+                    out.push_str(&cell.sgr());
+                    pen = Some(*cell);
+                }
+
+                let mut buf = [0; 4];
+                self.put(&mut out, row + y, col + x, cell.glyph.encode_utf8(&mut buf));
+            }
+        }
+
+        if pen.is_some() {
+            out.push_str("\x1B[0m");
+        }
+
+        self.last = Some(frame.clone());
+        Screen::flush(&out);
+    }
+
+    fn line_row(&self, num: usize) -> usize {
+        self.origin.0 + self.size.1 + BORDERS + 1 + num     // leave an empty line after the box
+    }
+
+    fn write_line(&mut self, out: &mut String, num: usize, line: &str) {
+        while self.line_row(num) > self.rows && self.origin.0 > 1 {     // scroll everything up to make room
+            let _ = write!(out, "\x1B[{};1H\r\n", self.rows);
+            self.origin.0 -= 1;
+        }
+
+        let row = self.line_row(num).min(self.rows);
+        self.move_to(out, row, 1);
+        let _ = write!(out, "\x1B[K{}{}", multiply(" ", self.origin.1 - 1), line);
+        self.cursor = None;     // tabs & colors make it hard to say where the cursor is
+
+        while self.lines.len() <= num {
+            self.lines.push(String::new());
+        }
+
+        self.lines[num] = line.to_owned();
+    }
+
+    fn print_line(&mut self, out: &mut String, num: usize, line: &str) {    // only if it's changed
+        if self.lines.get(num).map(|old| old != line).unwrap_or(true) {
+            self.write_line(out, num, line);
+        }
+    }
+
+    // status line (below the HUD) which keeps getting replaced
+    pub fn print_status(&mut self, msg: &str, color: Option<&str>) {
+        let mut out = String::new();
+        self.print_line(&mut out, 1, &colored(msg, color));
+        Screen::flush(&out);
+    }
+
+    // messages are appended below the status line
+    pub fn print_msg(&mut self, msg: &str, color: Option<&str>) {
+        let mut out = String::new();
+        let num = self.lines.len().max(2);
+        self.write_line(&mut out, num, &colored(msg, color));
+        Screen::flush(&out);
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {    // leave the cursor below everything we've drawn (for whatever comes next)
+        let row = self.line_row(self.lines.len()).min(self.rows);
+        Screen::flush(&format!("\x1B[{};1H\r\n", row));
+    }
+}
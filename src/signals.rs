@@ -0,0 +1,79 @@
+use keyevents::{restore_terminal, resume_raw_mode};
+use libc::{c_int, pthread_sigmask, raise, sigaddset, sigemptyset, signal, sighandler_t};
+use libc::{SIG_DFL, SIG_UNBLOCK, SIGCONT, SIGHUP, SIGINT, SIGQUIT, SIGTERM, SIGTSTP, SIGWINCH};
+use std::{mem, panic, ptr};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// signal handlers can't do much, so they just raise flags which are checked in the game loop
+static RESIZED: AtomicBool = AtomicBool::new(false);
+static RESUMED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_resize(_: c_int) {
+    RESIZED.store(true, Ordering::SeqCst);
+}
+
+// leave the terminal the way we found it, and then die the way we were asked to
+extern "C" fn on_terminate(sig: c_int) {
+    restore_terminal();
+    unsafe {
+        signal(sig, SIG_DFL);
+        raise(sig);
+    }
+}
+
+extern "C" fn on_suspend(_: c_int) {
+    restore_terminal();
+    unsafe {
+        let mut set = mem::zeroed();    // the signal's blocked while we're handling it, so unblock it first
+        sigemptyset(&mut set);
+        sigaddset(&mut set, SIGTSTP);
+        signal(SIGTSTP, SIG_DFL);
+        pthread_sigmask(SIG_UNBLOCK, &set, ptr::null_mut());
+        // we're stopped right here until someone sends SIGCONT (`fg` in the shell), unless there's no one
+        // around to do that (like when the shell's gone), in which case the signal is simply discarded
+        raise(SIGTSTP);
+        signal(SIGTSTP, handler(on_suspend));
+    }
+
+    on_continue(SIGCONT);   // either way, we're back
+}
+
+extern "C" fn on_continue(_: c_int) {
+    resume_raw_mode();
+    RESIZED.store(true, Ordering::SeqCst);      // the screen needs to be redrawn anyway
+    RESUMED.store(true, Ordering::SeqCst);
+}
+
+fn handler(function: extern "C" fn(c_int)) -> sighandler_t {
+    function as sighandler_t
+}
+
+pub fn watch_signals() {    // start listening for resizes, suspensions and the likes of `kill`
+    unsafe {
+        signal(SIGWINCH, handler(on_resize));
+        for &sig in &[SIGTERM, SIGHUP, SIGINT, SIGQUIT] {
+            signal(sig, handler(on_terminate));
+        }
+
+        signal(SIGTSTP, handler(on_suspend));
+        signal(SIGCONT, handler(on_continue));
+    }
+
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {     // or else, the panic message gets lost in the raw terminal
+        restore_terminal();
+        default_hook(info);
+    }));
+}
+
+pub fn suspend() {      // same as pressing Ctrl-Z in a normal terminal
+    unsafe { raise(SIGTSTP) };
+}
+
+pub fn take_resize() -> bool {      // has the terminal been resized since we last checked?
+    RESIZED.swap(false, Ordering::SeqCst)
+}
+
+pub fn take_resume() -> bool {      // have we been resumed (after being suspended) since we last checked?
+    RESUMED.swap(false, Ordering::SeqCst)
+}
@@ -1,80 +1,303 @@
+use frame::{Cell, Color, Entity, Frame};
 use helpers::*;
 use libc::c_uint;
 use keyevents::Key;
-use rand::{thread_rng, Rng};
-use {CLIFF_SEPARATION, CLIFF_Y, HEIGHT, JUMPER_X, JUMPER_Y, TIMEOUT_MS, WIDTH};
+use rand::{Isaac64Rng, Rng, SeedableRng};
+use config::{Controls, GameConfig};
+use obstacles::ObstacleGenerator;
+use reach::Reach;
+use std::cmp::Ordering;
+
+pub const SUBCELLS: isize = 100;    // momentum is tracked in hundredths of a column
+const INVULNERABLE_TICKS: usize = 24;   // how long the jumper can pass through the cliffs after a hit
+pub const HEALTH_PER_LIFE: usize = 2;
+// (body, mask) of the jumper, where the mask has the part of the jumper in each cell - (L)egs, (A)rms
+// and the (H)ead, which is what hurts the most when it's hit
+const JUMPER: ([&str; 2], [&str; 2]) = ([" \\\\ // ",   // assume that it's the front view of a falling jumper
+                                         "==[O]=="],
+                                        [" LL LL ",
+                                         "AAHHHAA"]);
+const SHRUNK_JUMPER: ([&str; 2], [&str; 2]) = (["\\ /",
+                                                "-o-"],
+                                               ["L L",
+                                                "AHA"]);
+const POWER_UP_CHANCE: usize = 15;      // chance (in %) of a power-up showing up between two cliffs (at first)
+const MAGNET_RANGE: usize = 12;     // rows below the jumper from which the magnet pulls stuff
+const ROWS_PER_POINT: usize = 10;   // distance fallen for every point
+const COIN_VALUE: usize = 5;
+const THROW_ATTEMPTS: usize = 10;   // layouts tried before giving up on throwing the cliffs (for now)
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BodyPart {     // (from the least to the most painful)
+    Leg,
+    Arm,
+    Head,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Collision {      // where the jumper ran into a cliff
+    pub body_part: BodyPart,
+    pub cell: (usize, usize),   // (x, y) inside the box
+    pub cliff_id: usize,
+}
+
+impl BodyPart {
+    // how much health a hit costs (a head hit costs a whole life, while the limbs only get grazed)
+    pub fn damage(&self) -> usize {
+        match *self {
+            BodyPart::Head => HEALTH_PER_LIFE,
+            _ => 1,
+        }
+    }
+}
+
+impl Collision {
+    pub fn message(&self) -> &'static str {
+        match self.body_part {
+            BodyPart::Leg => "There goes your leg!",
+            BodyPart::Arm => "You tore your arm off!",
+            BodyPart::Head => "You're now headless!",
+        }
+    }
+}
 
 #[derive(Clone, Debug)]
-struct Jumper {
+pub struct Jumper {
     area: FallArea,
+    step: (usize, usize),   // how far the jumper moves for every keypress
+    color: Color,
     x_pos: usize,
     y_pos: usize,
     body: Vec<String>,
+    mask: Vec<Vec<Option<BodyPart>>>,   // the part of the jumper in each cell (nothing's there for `None`)
     size: (usize, usize),
+    // falling speed (in cliff shifts for every tick) relative to the cliffs, where a negative value means that
+    // the jumper's braking and a positive value means that it's diving (always zero unless diving is allowed)
+    fall: isize,
+    dive: isize,    // the fastest it can dive
+    depth: isize,   // how far (in cliff shifts) it's gotten away from its usual place on the screen
+    // sideways momentum (everything's in hundredths of a column, and the jumper's drawn at the nearest one)
+    controls: Controls,
+    exact_x: isize,
+    velocity: isize,    // for every tick
+    thrust: isize,
+    drag: isize,
+    max_speed: isize,
 }
 
 impl Jumper {
-    fn new(fall_area: FallArea) -> Jumper {
-        let body = [" \\\\ // ",
-                    "==[O]=="];   // assume that it's the front view of a falling jumper
-        let size = (body[0].len(), body.len());
-        Jumper {
+    pub fn new(fall_area: FallArea, config: &GameConfig) -> Jumper {
+        let mut jumper = Jumper {
             area: fall_area,
+            step: (config.jumper_x, config.jumper_y),
+            color: config.theme.jumper,
             x_pos: (fall_area.width.0 / 2),
             y_pos: (fall_area.height.0 / 4),
-            body: body
-                  .iter()
-                  .map(|&string| string.to_owned())
-                  .collect(),
-            size: size
+            body: vec![],
+            mask: vec![],
+            size: (0, 0),
+            fall: 0,
+            dive: config.dive as isize,
+            depth: 0,
+            controls: config.controls,
+            exact_x: (fall_area.width.0 / 2) as isize * SUBCELLS,
+            velocity: 0,
+            thrust: config.thrust as isize,
+            drag: config.drag as isize,
+            max_speed: config.max_speed as isize,
+        };
+
+        jumper.dress(JUMPER);
+        jumper
+    }
+
+    // changes the jumper's looks (and its mask), while keeping it where it was
+    fn dress(&mut self, (body, mask): ([&str; 2], [&str; 2])) {
+        let old_width = self.size.0;
+        self.body = body.iter().map(|&string| string.to_owned()).collect();
+        self.mask = mask.iter()
+                        .map(|row| row.chars().map(|part| match part {
+                            'L' => Some(BodyPart::Leg),
+                            'A' => Some(BodyPart::Arm),
+                            'H' => Some(BodyPart::Head),
+                            _ => None,
+                        }).collect())
+                        .collect();
+        self.size = (body[0].chars().count(), body.len());
+        if old_width > 0 {      // (same center, as long as the walls allow it)
+            let center = self.x_pos + old_width / 2;
+            let x_pos = center.saturating_sub(self.size.0 / 2).max(1);
+            self.x_pos = x_pos.min(self.area.width.0 - self.size.0 - 1);
+            self.exact_x = self.x_pos as isize * SUBCELLS;
         }
     }
 
-    // base frame over which subsequent frames are drawn
-    fn draw(&self) -> Vec<String> {
-        let (y_pos, fall_area) = (self.y_pos, self.area);
-        let (body_width, body_height) = (self.size.0, self.size.1);
-        (0..fall_area.height.0).map(|i| {
-            if i < y_pos || i >= y_pos + body_height {
-                multiply(" ", fall_area.width.0)
-            } else {
-                multiply(" ", self.x_pos) +
-                &self.body[i - y_pos] +
-                &multiply(" ", fall_area.width.0 - (self.x_pos + body_width))
-            }
-        }).collect()
+    pub fn position(&self) -> (usize, usize) {
+        (self.x_pos, self.y_pos)
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        self.size
+    }
+
+    pub fn step(&self) -> (usize, usize) {
+        self.step
+    }
+
+    pub fn area(&self) -> FallArea {
+        self.area
+    }
+
+    // size of the jumper when it's not shrunk
+    pub fn full_size(&self) -> (usize, usize) {
+        (JUMPER.0[0].chars().count(), JUMPER.0.len())
+    }
+
+    // base frame over which subsequent frames are drawn (the jumper's left out when it's blinking)
+    fn draw(&self, hidden: bool) -> Frame {
+        let mut frame = Frame::new(self.area.width.0, self.area.height.0);
+        let mut style = Cell::new(' ', self.color, Entity::Jumper);
+        style.attrs.bold = true;
+        for (i, line) in self.body.iter().enumerate().filter(|_| !hidden) {
+            frame.put_str(self.x_pos, self.y_pos + i, line, style);
+        }
+
+        frame
     }
 
     fn shift(&mut self, key: Key) {
         match key {
-            Key::Right if (self.x_pos + JUMPER_X + self.size.0) < self.area.width.0 => {
-                self.x_pos += JUMPER_X;
+            Key::Right | Key::Left if self.controls == Controls::Momentum => {
+                let thrust = if key == Key::Right { self.thrust } else { -self.thrust };
+                self.velocity = (self.velocity + thrust).max(-self.max_speed).min(self.max_speed);
             },
-            Key::Left if (self.x_pos as isize - JUMPER_X as isize) > 0 => {
-                self.x_pos -= JUMPER_X;
+            Key::Right if (self.x_pos + self.step.0 + self.size.0) < self.area.width.0 => {
+                self.x_pos += self.step.0;
             },
-            Key::Up if (self.y_pos as isize - JUMPER_Y as isize) > 0 => {
-                // self.y_pos -= JUMPER_Y;
-            },
-            Key::Down if (self.y_pos + JUMPER_Y + self.size.1) < self.area.height.0 => {
-                // self.y_pos += JUMPER_Y;
+            Key::Left if (self.x_pos as isize - self.step.0 as isize) > 0 => {
+                self.x_pos -= self.step.0;
             },
+            // 2-DOF doesn't move the jumper right away (it only changes how fast it falls)
+            Key::Up if self.dive > 0 => self.fall = (self.fall - 1).max(-1),    // braking all the way stops it
+            Key::Down if self.dive > 0 => self.fall = (self.fall + 1).min(self.dive),
             _ => (),
         }
     }
+
+    // goes wherever the momentum takes it for a tick (and the air slows it down, all the way to a halt)
+    fn glide(&mut self) {
+        if self.velocity == 0 {     // (which is always the case when it's moving in steps)
+            return
+        }
+
+        let (exact_x, velocity) = self.coast((self.exact_x, self.velocity), 0);
+        self.exact_x = exact_x;
+        self.velocity = velocity;
+        self.x_pos = ((self.exact_x + SUBCELLS / 2) / SUBCELLS) as usize;
+    }
+
+    // (position, velocity) of the jumper, in hundredths of a column (only for the momentum controls)
+    pub fn momentum(&self) -> (isize, isize) {
+        (self.exact_x, self.velocity)
+    }
+
+    // where the momentum takes the jumper in a tick from the given (position, velocity), after it's been
+    // pushed to the right (1), to the left (-1) or not at all (0)
+    pub fn coast(&self, (exact_x, velocity): (isize, isize), push: isize) -> (isize, isize) {
+        let velocity = (velocity + push * self.thrust).max(-self.max_speed).min(self.max_speed);
+        if velocity == 0 {
+            return (exact_x, 0)
+        }
+
+        let (leftmost, rightmost) = (SUBCELLS, (self.area.width.0 - self.size.0 - 1) as isize * SUBCELLS);
+        let exact_x = exact_x + velocity;
+        if exact_x <= leftmost || exact_x >= rightmost {    // the walls stop it dead
+            (exact_x.max(leftmost).min(rightmost), 0)
+        } else if self.drag > 0 {
+            let loss = (velocity.abs() * self.drag / 100).max(1).min(velocity.abs());
+            (exact_x, velocity - velocity.signum() * loss)
+        } else {
+            (exact_x, velocity)
+        }
+    }
+
+    // where the jumper comes to a halt (in hundredths of a column) if it's left alone from the given state
+    fn rest(&self, (mut exact_x, mut velocity): (isize, isize)) -> isize {
+        for _ in 0..1000 {      // (the walls stop it anyway, when there's no drag)
+            if velocity == 0 {
+                break
+            }
+
+            let (x, v) = self.coast((exact_x, velocity), 0);
+            exact_x = x;
+            velocity = v;
+        }
+
+        exact_x
+    }
+
+    // The key that takes the jumper (its left edge) towards the given column, if it's not already as close
+    // as it can get. With momentum, it's the push (if any) that brings the jumper to a halt closest to the
+    // column, so it eases off when it's fast enough, and pushes the other way when it's going too far.
+    pub fn heading(&self, column: isize) -> Option<Key> {
+        if self.controls == Controls::Steps {
+            let (diff, step) = (column - self.x_pos as isize, self.step.0 as isize);
+            return match diff {
+                _ if diff * 2 > step => Some(Key::Right),   // a step in that direction will take it closer
+                _ if diff * 2 < -step => Some(Key::Left),
+                _ => None,
+            }
+        }
+
+        let state = (self.exact_x, self.velocity);
+        let miss = |push: isize| (column * SUBCELLS - self.rest(self.coast(state, push))).abs();
+        let (left, still, right) = (miss(-1), miss(0), miss(1));
+        match still {
+            _ if right < still && right <= left => Some(Key::Right),
+            _ if left < still => Some(Key::Left),
+            _ => None,
+        }
+    }
+
+    // the jumper's top row (and the number of times the cliffs are shifted) for each of the coming ticks,
+    // if it keeps falling as fast as it is now (or at its usual pace, if it's braking)
+    pub fn falls(&self, rise: usize) -> impl Iterator<Item = (usize, usize)> {
+        let mut jumper = self.clone();
+        jumper.fall = jumper.fall.max(0);
+        (0..).map(move |_| {
+            let shifts = jumper.fall(rise);
+            (jumper.y_pos, shifts)
+        })
+    }
+
+    // Falls for a tick, and returns the number of times the cliffs should be shifted for the camera to
+    // keep up. The camera catches up as the jumper gets further away from its usual place (so a diving
+    // jumper sinks down the screen, while the cliffs come faster), but it never lets the jumper out of sight.
+    fn fall(&mut self, rise: usize) -> usize {
+        let home = self.area.height.0 / 4;
+        let (highest, lowest) = (-(((home - 1) / rise) as isize),
+                                 ((self.area.height.0 - self.size.1 - 1 - home) / rise) as isize);
+        let falling = self.depth + 1 + self.fall;
+        let camera = (1 + self.depth / 2).max(0);
+        self.depth = (falling - camera).max(highest).min(lowest);
+        self.y_pos = (home as isize + self.depth * rise as isize) as usize;
+        (falling - self.depth) as usize
+    }
 }
 
 #[derive(Clone, Debug)]
-struct Cliff {
+pub struct Cliff {
+    id: usize,      // unique for every cliff in a game (so that we know who's who in the frame)
     x_pos: usize,   // used for initial random positioning of the cliff (restricted to window's width)
     y_pos: usize,   // though it has only one DOF, the cliffs should move upward over consecutive frames
     body: Vec<String>,
+    mask: Vec<Vec<bool>>,   // whether there's rock in each cell
     size: (usize, usize),   // cliff size is random and restricted to half the window's width (and a height of "4")
 }
 
 impl Cliff {
-    fn new(jumper: &Jumper) -> Cliff {   // jumper's position is necessary to throw cliffs at him!
-        let mut rng = thread_rng();
+    // jumper's position is necessary to throw cliffs at him! (and the game's RNG for placing them)
+    pub fn new<R: Rng>(id: usize, jumper: &Jumper, rng: &mut R) -> Cliff {
         let (full_width, full_height) = (jumper.area.width.0, jumper.area.height.0);
         let half_width = full_width / 2;
         let x_size: usize = rng.gen_range(half_width / 5, half_width - half_width / 5);
@@ -82,7 +305,7 @@ impl Cliff {
 
         let left_side = {
             let jumper_side = jumper.x_pos.le(&half_width);
-            rng.choose(&[true, false, jumper_side]).unwrap().clone()    // increase the chance of hitting the jumper
+            *rng.choose(&[true, false, jumper_side]).unwrap()    // increase the chance of hitting the jumper
         };
 
         let x_pos: usize = match left_side {
@@ -90,9 +313,19 @@ impl Cliff {
             false => full_width - x_size - rng.gen_range(1, full_width / 3) - 1,
         };
 
+        let mut cliff = Cliff::block(x_pos, (x_size + 2, y_size), jumper);
+        cliff.id = id;
+        cliff
+    }
+
+    // a cliff of the given size (including its edges) at the bottom of the box (the game gives it an ID
+    // when it's thrown), where the width should be at least 3 and the height at least 4
+    pub fn block(x_pos: usize, size: (usize, usize), jumper: &Jumper) -> Cliff {
+        let (x_size, y_size) = (size.0 - 2, size.1);
         Cliff {
-            x_pos: x_pos,
-            y_pos: full_height,     // initial position of any cliff is at the bottom
+            id: 0,
+            x_pos,
+            y_pos: jumper.area.height.0,    // initial position of any cliff is at the bottom
             body: (0..y_size)
                   .map(|part| {
                       if part == 0 {                                        // cliff of size (2, 5)
@@ -107,16 +340,35 @@ impl Cliff {
                           "|".to_owned() + &multiply("O", x_size) + "|"
                       }
                   }).collect(),
-            size: (x_size + 2, y_size),
+            // solid all the way, except for the rounded corners
+            mask: (0..y_size)
+                  .map(|part| (0..x_size + 2).map(|col| {
+                      !((part == 0 || part == y_size - 1) && (col == 0 || col == x_size + 1))
+                  }).collect())
+                  .collect(),
+            size,
         }
     }
 
-    fn shift(&mut self) {
-        let diff = self.y_pos as isize - CLIFF_Y as isize;
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn position(&self) -> (usize, usize) {
+        (self.x_pos, self.y_pos)
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        self.size
+    }
+
+    fn shift(&mut self, lines: usize) {
+        let diff = self.y_pos as isize - lines as isize;
         if diff >= 0 {
-            self.y_pos -= CLIFF_Y;
+            self.y_pos -= lines;
         } else {
             self.body = self.body[1..].to_vec();
+            self.mask = self.mask[1..].to_vec();
             self.size = (self.size.0, self.size.1 - 1);
         }
     }
@@ -126,93 +378,438 @@ impl Cliff {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Power {
+    Shield,     // absorbs one hit
+    SlowMotion,     // the cliffs take their time
+    Shrink,     // a smaller jumper (which fits through smaller gaps)
+    Magnet,     // pulls the stuff below towards the jumper
+}
+
+const POWERS: [(Power, &str, char, usize); 4] = [     // (power, name, symbol, duration in ticks)
+    (Power::Shield, "SHIELD", 'S', 200),
+    (Power::SlowMotion, "SLOW", 'T', 100),
+    (Power::Shrink, "SHRINK", 'v', 120),
+    (Power::Magnet, "MAGNET", 'M', 150),
+];
+
+impl Power {
+    fn info(&self) -> (Power, &'static str, char, usize) {
+        *POWERS.iter().find(|&&(power, _, _, _)| power == *self).unwrap_or(&POWERS[0])
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.info().1
+    }
+
+    pub fn duration(&self) -> usize {
+        self.info().3
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PowerUp {    // power-ups float up along with the cliffs, waiting to be caught
+    power: Power,
+    x_pos: usize,
+    y_pos: usize,
+}
+
+impl PowerUp {
+    // somewhere below the box (like the cliffs)
+    pub fn new<R: Rng>(jumper: &Jumper, rng: &mut R) -> PowerUp {
+        let (full_width, full_height) = (jumper.area.width.0, jumper.area.height.0);
+        PowerUp {
+            power: *rng.choose(&[Power::Shield, Power::SlowMotion, Power::Shrink, Power::Magnet]).unwrap(),
+            x_pos: rng.gen_range(1, full_width - 4),
+            y_pos: full_height,
+        }
+    }
+
+    pub fn power(&self) -> Power {
+        self.power
+    }
+
+    pub fn position(&self) -> (usize, usize) {
+        (self.x_pos, self.y_pos)
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        (3, 1)
+    }
+
+    fn body(&self) -> String {
+        format!("({})", self.power.info().2)
+    }
+
+}
+
+#[derive(Clone, Debug)]
+pub struct Coin {   // coins float up in the gaps between the cliffs (like the power-ups)
+    x_pos: usize,
+    y_pos: usize,
+}
+
+impl Coin {
+    pub fn position(&self) -> (usize, usize) {
+        (self.x_pos, self.y_pos)
+    }
+}
+
+// whether something (a single row of the given width) at the position is touching the jumper
+fn touching((x_pos, y_pos): (usize, usize), width: usize, jumper: &Jumper) -> bool {
+    y_pos >= jumper.y_pos && y_pos < jumper.y_pos + jumper.size.1 &&
+    x_pos < jumper.x_pos + jumper.size.0 && jumper.x_pos < x_pos + width
+}
+
+// the magnet pulls stuff one column closer to the jumper (if it's not too far below)
+fn pull(x_pos: &mut usize, y_pos: usize, width: usize, jumper: &Jumper) {
+    let center = jumper.x_pos + jumper.size.0 / 2;
+    if y_pos >= jumper.y_pos && y_pos < jumper.y_pos + jumper.size.1 + MAGNET_RANGE {
+        match (*x_pos + width / 2).cmp(&center) {
+            Ordering::Less => *x_pos += 1,
+            Ordering::Greater => *x_pos -= 1,
+            Ordering::Equal => (),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Points {     // where the score comes from
+    pub distance: usize,    // rows fallen
+    pub cliffs: usize,      // cliffs cleared
+    pub coins: usize,       // coins collected
+}
+
+impl Points {
+    pub fn total(&self) -> usize {
+        self.distance / ROWS_PER_POINT + self.cliffs + self.coins * COIN_VALUE
+    }
+
+    // the score, and how it was made (like "57 = 12 (distance) + 30 (cliffs) + 3 x 5 (coins)")
+    pub fn breakdown(&self) -> String {
+        format!("{} = {} (distance) + {} (cliffs) + {} x {} (coins)",
+                self.total(), self.distance / ROWS_PER_POINT, self.cliffs, self.coins, COIN_VALUE)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Input {    // things that can move the game forward
+    Key(Key),   // shifts the jumper
+    Tick,       // shifts the cliffs (the poll has timed out)
+}
+
+#[derive(Clone, Debug)]
+pub struct FrameState {     // everything a front-end needs for showing a frame (nothing's printed by the game)
+    pub frame: Frame,       // what's inside the box
+    pub score: usize,
+    pub points: Points,     // (what the score's made of)
+    pub speed: c_uint,
+    pub collision: Option<Collision>,   // what the jumper ran into (the game has ended when this is set)
+    pub health: (usize, usize),     // (what's left, what the jumper started with)
+    pub powers: Vec<(Power, usize)>,    // powers that the jumper has right now (and the ticks they have left)
+}
+
+impl FrameState {
+    pub fn is_over(&self) -> bool {
+        self.collision.is_some()
+    }
+}
+
 pub struct Game {   // struct to hold all the objects required for a new game
     // difficulty parameters (inversely proportional to difficulty)
     pub poll_timeout: c_uint,       // how fast the cliffs come & try to bang at you!
     cliff_separation: usize,    // how long before a cliff appears!
+    config: GameConfig,     // initial values of the above (the game gets harder from there) & other stuff
     jumper: Jumper,     // jumper is always necessary to draw the picture
     cliffs: Vec<Cliff>,     // vector of all cliffs on the current frame
     num_cliffs: usize,      // just to stop finding the length every time we update the cliffs
+    cliffs_thrown: usize,   // total number of cliffs so far (for their IDs)
     line_since_last: usize,     // line since the last cliff was thrown (required because they're equally spaced)
-    collision: Option<&'static str>,    // collision message which is triggered when the game ends
-    score: usize,   // score that you see on the lower left corner
-    // FIXME: yuck! these should be replaced with cursor controllers "ASAP!"
-    side: String,
-    top: String,
-    bottom: String,
+    last_height: usize,     // height of the last bunch of cliffs (the tallest one in there)
+    pending: usize,     // shifts left in the current tick (which the cliffs thrown right now will also get)
+    swept: usize,       // rows that the cliffs have risen past the jumper in the last tick (all checked for hits)
+    generator: Box<dyn ObstacleGenerator>,      // comes up with the cliffs
+    gap_filled: bool,       // whether we've put something in the gap after the last cliff
+    power_ups: Vec<PowerUp>,
+    coins: Vec<Coin>,
+    powers: Vec<(Power, usize)>,    // (power, ticks left)
+    collision: Option<Collision>,   // the hit which ended the game
+    health: usize,
+    invulnerable: usize,    // ticks left before the cliffs can hurt the jumper again
+    points: Points,     // score that you see on the lower left corner
+    ticks: usize,   // number of times the cliffs have been shifted (i.e., the game's clock)
+    seed: u64,      // seed for the RNG (same seed => same cliffs, so that a run can be replayed)
+    rng: Isaac64Rng,
 }
 
 impl Game {
-    pub fn new(poll_timeout: c_uint, cliff_sep: usize) -> Result<Game, &'static str> {
-        let fall_area = match FallArea::new(WIDTH, HEIGHT) {
-            Ok(area) => area,
-            Err(err) => return Err(err),
-        };
+    pub fn new(config: GameConfig, seed: u64) -> Result<Game, &'static str> {
+        let fall_area = FallArea::new(&config)?;
+        Ok(Game::with_area(fall_area, config, seed))
+    }
 
-        let jumper = Jumper::new(fall_area);
-        let cliff = Cliff::new(&jumper);
-
-        let top_indent = fall_area.height.1 / 2;
-        let bottom_indent = fall_area.height.1 - top_indent;
-        let box_width = fall_area.width.0;
-        let left_indent = fall_area.width.1 / 2;
-
-        // draw the dashed box for the frames to be drawn inside
-        let dashes = multiply(" ", left_indent) + &multiply("-", box_width) + "--";
-        // base & lid of the box
-        let user_env_top = multiply("\r\n", top_indent) + &dashes;
-        let user_env_bottom = dashes + &multiply("\r\n", bottom_indent);
-
-        Ok(Game {
-            poll_timeout: poll_timeout,
-            cliff_separation: cliff_sep,
-            jumper: jumper,
-            cliffs: vec![cliff],
-            num_cliffs: 1,
+    // create a game for the given area (this doesn't touch the terminal at all)
+    pub fn with_area(fall_area: FallArea, config: GameConfig, seed: u64) -> Game {
+        Game::with_generator(fall_area, config, seed, config.obstacles.generator())
+    }
+
+    // same as above, but with some other generator for the cliffs (instead of the one in the config)
+    pub fn with_generator(fall_area: FallArea, config: GameConfig, seed: u64,
+                          generator: Box<dyn ObstacleGenerator>) -> Game {
+        let rng = Isaac64Rng::from_seed(&[seed][..]);
+        let jumper = Jumper::new(fall_area, &config);
+        let mut game = Game {
+            poll_timeout: config.timeout,
+            cliff_separation: config.cliff_separation,
+            config,
+            jumper,
+            cliffs: vec![],
+            num_cliffs: 0,
+            cliffs_thrown: 0,
             line_since_last: 0,
+            last_height: 0,
+            pending: 0,
+            swept: 0,
+            generator,
+            gap_filled: false,
+            power_ups: vec![],
+            coins: vec![],
+            powers: vec![],
             collision: None,
-            score: 0,
-            // YUCK!!!
-            side: multiply(" ", left_indent),
-            top: user_env_top,
-            bottom: user_env_bottom,
-        })
+            health: config.lives * HEALTH_PER_LIFE,
+            invulnerable: 0,
+            points: Points::default(),
+            ticks: 0,
+            seed,
+            rng,
+        };
+
+        game.throw();   // (if there's no way past the first cliff, then it's thrown once the separation's passed)
+        game
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    pub fn area(&self) -> FallArea {
+        self.jumper.area
+    }
+
+    pub fn jumper(&self) -> &Jumper {
+        &self.jumper
+    }
+
+    pub fn cliffs(&self) -> &[Cliff] {
+        &self.cliffs
+    }
+
+    pub fn power_ups(&self) -> &[PowerUp] {
+        &self.power_ups
+    }
+
+    pub fn has_power(&self, power: Power) -> bool {
+        self.powers.iter().any(|&(active, _)| active == power)
+    }
+
+    pub fn coins(&self) -> &[Coin] {
+        &self.coins
+    }
+
+    pub fn score(&self) -> usize {
+        self.points.total()
+    }
+
+    pub fn points(&self) -> Points {
+        self.points
     }
 
-    pub fn is_running(&mut self) -> bool {
-        let mut frame = self.jumper.draw();
-        // decrement the poll timeout for every 8 units of score
-        self.poll_timeout = TIMEOUT_MS - (self.score / 8) as c_uint;
-        // decrement the cliff separation for every 100 units of score (I don't think people can make it that far)
-        self.cliff_separation = CLIFF_SEPARATION - self.score / 100;
-        self.draw_cliffs(&mut frame, false);    // `true` means GOD mode! (ehm, debug mode)
-
-        match self.collision {
-            Some(msg) => {
-                self.collision = None;      // workaround for printing the last frame while quitting
-                self.draw_cliffs(&mut frame, true);
-                self.print_frame(&frame);
-                print_msg(msg, Some("Y"));
-                false
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn ticks(&self) -> usize {
+        self.ticks
+    }
+
+    // advance the game by one input and return the resulting frame (the game stays frozen once it's over)
+    pub fn step(&mut self, input: Input) -> FrameState {
+        if self.collision.is_none() {
+            match input {
+                Input::Key(key) => {
+                    self.swept = 0;
+                    self.jumper_shift(key)
+                },
+                Input::Tick => {
+                    self.ticks += 1;
+                    self.invulnerable = self.invulnerable.saturating_sub(1);
+                    self.wear_off();
+                    self.jumper.glide();
+                    let (row, rise) = (self.jumper.y_pos, self.config.cliff_y);
+                    let shifts = self.jumper.fall(rise);
+                    for pending in (0..shifts).rev() {
+                        self.pending = pending;
+                        self.cliffs_shift()
+                    }
+
+                    // (the jumper never rises faster than the cliffs, so they always get closer to its top)
+                    self.swept = shifts * rise + self.jumper.y_pos - row;
+                    self.regrow();
+                },
+            }
+        }
+
+        self.draw()
+    }
+
+    pub fn draw(&mut self) -> FrameState {
+        let mut frame = self.jumper.draw(self.invulnerable % 6 >= 3);     // blinks while it's invulnerable
+        // decrement the poll timeout for every 8 cliffs (the difficulty doesn't care about the coins)
+        let cleared = self.points.cliffs;
+        let timeout = self.config.timeout.saturating_sub((cleared / 8) as c_uint);
+        self.poll_timeout = match self.has_power(Power::SlowMotion) {
+            true => timeout * 2,
+            false => timeout,
+        };
+
+        // decrement the cliff separation for every 100 cliffs (I don't think people can make it that far)
+        self.cliff_separation = self.config.cliff_separation.saturating_sub(cleared / 100);
+        if self.collision.is_none() {
+            self.catch_power_ups();
+            let jumper = &self.jumper;
+            let count = self.coins.len();
+            self.coins.retain(|coin| !touching(coin.position(), 1, jumper));
+            self.points.coins += count - self.coins.len();
+        }
+
+        self.draw_pickups(&mut frame);
+        self.draw_cliffs(&mut frame);
+        match self.hit() {      // ignore this for GOD mode! (ehm, debug mode)
+            Some(_) if self.collision.is_none() && self.invulnerable == 0 && self.has_power(Power::Shield) => {
+                self.powers.retain(|&(power, _)| power != Power::Shield);
+                self.invulnerable = INVULNERABLE_TICKS;     // (so that it can get away from the cliff)
             },
-            None => {
-                self.print_frame(&frame);
-                true
+            Some(hit) if self.collision.is_none() && self.invulnerable == 0 => {
+                let damage = match self.config.lives {
+                    1 => self.health,   // (a single life doesn't get any second chances)
+                    _ => hit.body_part.damage(),
+                };
+
+                self.health = self.health.saturating_sub(damage);
+                match self.health {
+                    0 => self.collision = Some(hit),
+                    _ => self.invulnerable = INVULNERABLE_TICKS,
+                }
             },
+            _ => (),
+        }
+
+        FrameState {
+            frame,
+            score: self.points.total(),
+            points: self.points,
+            speed: self.config.timeout - timeout,
+            collision: self.collision,
+            health: (self.health, self.config.lives * HEALTH_PER_LIFE),
+            powers: self.powers.clone(),
         }
     }
 
-    pub fn print_frame(&self, frame: &[String]) {       // gameplay inside an outlined box
-        println!("{}", self.top);
-        print!("\r{}|{}", &self.side, frame.join(&("|\n\r".to_owned() + &self.side + "|")));
-        print!("\r{}", self.bottom);
-        print_msg(&format!("SCORE: {}\tSPEED: {}", self.score, TIMEOUT_MS - self.poll_timeout), Some("G"));
+    // the powers are only good for a while
+    fn wear_off(&mut self) {
+        for power in &mut self.powers {
+            power.1 -= 1;
+        }
+
+        self.powers.retain(|&(_, left)| left > 0);
+    }
+
+    // The jumper gets back to its usual size once the shrink has worn off, but only when there's room for
+    // it (it stays small for as long as growing would run it into a cliff that it was safely passing by).
+    fn regrow(&mut self) {
+        if self.has_power(Power::Shrink) || self.jumper.size == self.jumper.full_size() {
+            return
+        }
+
+        let mut grown = self.jumper.clone();
+        grown.dress(JUMPER);
+        if self.hits(&grown).is_none() {
+            self.jumper = grown;
+        }
+    }
+
+    fn catch_power_ups(&mut self) {
+        let jumper = &self.jumper;
+        let (caught, rest): (Vec<_>, _) = self.power_ups.drain(..).partition(|power_up| {
+            touching(power_up.position(), 3, jumper)
+        });
+        self.power_ups = rest;
+        for power_up in caught {
+            let power = power_up.power;
+            self.powers.retain(|&(active, _)| active != power);     // (catching it again starts it afresh)
+            self.powers.push((power, power.duration()));
+            if power == Power::Shrink {
+                self.jumper.dress(SHRUNK_JUMPER);
+            }
+        }
+    }
+
+    // puts a row of coins in the middle of the gap between the last two cliffs (and a power-up, if we're lucky)
+    fn fill_gap(&mut self) {
+        let speed = (self.config.timeout - self.poll_timeout.min(self.config.timeout)) as usize;
+        // the game gets harder as it goes, so the power-ups show up more often (but not too often)
+        let power_up = match self.rng.gen_range(0, 100) < (POWER_UP_CHANCE + speed).min(50) {
+            true => Some(PowerUp::new(&self.jumper, &mut self.rng)),
+            false => None,
+        };
+
+        let (width, height) = (self.jumper.area.width.0, self.jumper.area.height.0);
+        let count: usize = self.rng.gen_range(3, 7);
+        let start = self.rng.gen_range(1, width - 2 * count);
+        for x_pos in (0..count).map(|i| start + 2 * i) {    // (spaced out, and out of the power-up's way)
+            if !power_up.as_ref().is_some_and(|power_up| x_pos + 1 >= power_up.x_pos && x_pos <= power_up.x_pos + 3) {
+                self.coins.push(Coin { x_pos, y_pos: height });
+            }
+        }
+
+        self.power_ups.extend(power_up);
+    }
+
+    // Throws a bunch of cliffs (from the generator) that the jumper can get past (along with the ones that
+    // are already on their way), and returns whether it did. When none of the attempts work out, the cliffs
+    // wait for the others to get out of the way (they're tried again on every shift after that, with more room).
+    fn throw(&mut self) -> bool {
+        let mut reach = Reach::new(&self.jumper, &self.config, self.pending);
+        if !reach.clears(&self.cliffs.iter().collect::<Vec<_>>()) {
+            reach = reach.anywhere();   // the jumper's in trouble already (nothing we can do about that)
+        }
+
+        let score = self.score();
+        for _ in 0..THROW_ATTEMPTS {
+            let bunch = self.generator.generate(&self.jumper, score, &mut self.rng);
+            if bunch.is_empty() || !reach.clears(&self.cliffs.iter().chain(&bunch).collect::<Vec<_>>()) {
+                continue
+            }
+
+            self.generator.accept();
+            self.last_height = bunch.iter().map(|cliff| cliff.size.1).max().unwrap_or(0);
+            for mut cliff in bunch {
+                cliff.id = self.cliffs_thrown;
+                self.cliffs.push(cliff);
+                self.cliffs_thrown += 1;
+                self.num_cliffs += 1;
+            }
+
+            self.line_since_last = 0;
+            return true
+        }
+
+        false
     }
 
     pub fn jumper_shift(&mut self, key: Key) {
         self.jumper.shift(key)
     }
-
     pub fn cliffs_shift(&mut self) {
         self.line_since_last += 1;
         let mut i = 0;
@@ -220,54 +817,168 @@ impl Game {
             if self.cliffs[i].erase_body() {
                 self.cliffs.remove(i);
                 self.num_cliffs -= 1;
-                self.score += 1;
+                self.points.cliffs += 1;
+                continue    // the next one has taken its place
             }
-            self.cliffs[i].shift();
+            self.cliffs[i].shift(self.config.cliff_y);
             i += 1;
         }
 
-        let last_cliff_size = self.cliffs[self.num_cliffs - 1].size.1;
+        let rise = self.config.cliff_y;
+        self.points.distance += rise;
+        self.power_ups.retain(|power_up| power_up.y_pos >= rise);   // (the ones that got away)
+        self.coins.retain(|coin| coin.y_pos >= rise);
+        for power_up in &mut self.power_ups {
+            power_up.y_pos -= rise;
+        }
+
+        for coin in &mut self.coins {
+            coin.y_pos -= rise;
+        }
+
+        if self.has_power(Power::Magnet) {
+            for power_up in &mut self.power_ups {
+                pull(&mut power_up.x_pos, power_up.y_pos, 3, &self.jumper);
+            }
+
+            for coin in &mut self.coins {
+                pull(&mut coin.x_pos, coin.y_pos, 1, &self.jumper);
+            }
+        }
+
+        let last_cliff_size = self.last_height;
         if self.line_since_last > last_cliff_size &&
-        self.line_since_last - last_cliff_size == self.cliff_separation {
-            self.line_since_last = 0;
-            self.cliffs.push(Cliff::new(&self.jumper));
-            self.num_cliffs += 1;
+        self.line_since_last - last_cliff_size >= self.cliff_separation &&     // (the separation shrinks as we go)
+        self.throw() {
+            self.gap_filled = false;
+        } else if !self.gap_filled && self.line_since_last >= last_cliff_size + self.cliff_separation / 2 {
+            self.gap_filled = true;
+            self.fill_gap();
         }
     }
 
-    pub fn draw_cliffs(&mut self, frame: &mut [String], ignore_collision: bool) {
-        let area = self.jumper.area;
-        fn collision(string: &str) -> Option<&'static str> {
-            for j in string.chars() {
-                match j {
-                    '=' => return Some("You tore your arm off!"),
-                    '\\' | '/' => return Some("There goes your leg!"),
-                    '[' | ']' | 'o' => return Some("You're now headless!"),
-                    _ => continue,
-                }
-            } None
+    fn draw_pickups(&self, frame: &mut Frame) {     // power-ups and coins
+        let mut style = Cell::new(' ', Color::Green, Entity::PowerUp);
+        style.attrs.bold = true;
+        for power_up in &self.power_ups {
+            frame.put_str(power_up.x_pos, power_up.y_pos, &power_up.body(), style);
         }
 
+        let mut style = Cell::new('$', Color::Yellow, Entity::Coin);
+        style.attrs.bold = true;
+        for coin in &self.coins {
+            frame.set(coin.x_pos, coin.y_pos, style);
+        }
+    }
+
+    fn draw_cliffs(&self, frame: &mut Frame) {
         for cliff in &self.cliffs {
-            let (x_pos, y_pos) = (cliff.x_pos, cliff.y_pos);
-            let (body_width, body_height) = (cliff.size.0, cliff.size.1);
-            for i in y_pos..(y_pos + body_height) {
-                if i < area.height.0 {
-                    let line = frame[i].clone();
-                    let (start, end) = (&line[..x_pos], &line[x_pos + body_width..]);
-                    frame[i] = start.to_owned() + &cliff.body[i - y_pos] + end;
-                    // we ignore the collision thing only for printing the last frame while quitting
-                    // well, this will also be useful for tests
-                    self.collision = match ignore_collision {
-                        true => None,
-                        false => collision(&line[x_pos..x_pos + body_width]),
+            let style = Cell::new(' ', self.config.theme.cliff, Entity::Cliff(cliff.id));
+            for (i, part) in cliff.body.iter().enumerate() {
+                frame.put_str(cliff.x_pos, cliff.y_pos + i, part, style);
+            }
+        }
+    }
+
+    // Overlaps the masks of the jumper and the cliffs (regardless of how they look on the screen), and
+    // returns the worst hit if there's any (the one that's first from the top left, when there are many).
+    // A tick can move the cliffs (and the jumper) by a few rows, so every row that the cliffs have risen
+    // past the jumper is checked, and the first of them that's got a hit is the one that counts.
+    pub fn hit(&self) -> Option<Collision> {
+        self.hits(&self.jumper)
+    }
+
+    fn hits(&self, jumper: &Jumper) -> Option<Collision> {
+        (0..=self.swept).rev().filter_map(|below| self.hits_at(jumper, below)).next()
+    }
+
+    // (with the cliffs as they were when they were the given rows below, relative to the jumper)
+    fn hits_at(&self, jumper: &Jumper, below: usize) -> Option<Collision> {
+        let mut worst: Option<Collision> = None;
+        for cliff in &self.cliffs {
+            for (i, row) in jumper.mask.iter().enumerate() {
+                for (j, part) in row.iter().enumerate() {
+                    let (x, y) = (jumper.x_pos + j, jumper.y_pos + i);
+                    let solid = match (x.checked_sub(cliff.x_pos), y.checked_sub(cliff.y_pos + below)) {
+                        (Some(col), Some(line)) => cliff.mask.get(line).and_then(|row| row.get(col)) == Some(&true),
+                        _ => false,
                     };
-                }
 
-                if self.collision.is_some() {
-                    return
+                    match *part {
+                        Some(body_part) if solid && worst.is_none_or(|hit| body_part > hit.body_part) => {
+                            worst = Some(Collision { body_part, cell: (x, y), cliff_id: cliff.id });
+                        },
+                        _ => (),
+                    }
                 }
             }
         }
+
+        worst
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use obstacles::ObstacleGenerator;
+
+    struct Nothing;     // (the cliffs are put in by hand)
+
+    impl ObstacleGenerator for Nothing {
+        fn generate(&mut self, _jumper: &Jumper, _score: usize, _rng: &mut Isaac64Rng) -> Vec<Cliff> {
+            vec![]
+        }
+    }
+
+    fn empty_game(config: GameConfig) -> Game {
+        Game::with_generator(FallArea::fit(&config, 40, 80).unwrap(), config, 0, Box::new(Nothing))
+    }
+
+    #[test]
+    fn shrunk_jumper_grows_back_only_when_theres_room() {
+        let mut game = empty_game(GameConfig::default());
+        game.jumper.dress(SHRUNK_JUMPER);
+        game.powers.push((Power::Shrink, 1));
+        // a cliff right next to the shrunk jumper (which will have risen up to it by the end of the tick)
+        let (x, y) = game.jumper.position();
+        let mut cliff = Cliff::block(x + game.jumper.size().0, (4, 6), &game.jumper);
+        cliff.y_pos = y + game.config.cliff_y;
+        game.cliffs.push(cliff);
+        game.num_cliffs = 1;
+
+        let state = game.step(Input::Tick);
+        assert!(!state.is_over() && state.health.0 == state.health.1);
+        assert!(!game.has_power(Power::Shrink));
+        assert_eq!(game.jumper.size(), (3, 2));
+
+        game.cliffs.clear();
+        game.num_cliffs = 0;
+        game.step(Input::Tick);
+        assert_eq!(game.jumper.size(), game.jumper.full_size());
+    }
+
+    #[test]
+    fn cliffs_cant_skip_past_the_jumper() {
+        let config = GameConfig { cliff_y: 7, ..GameConfig::default() };
+        let mut game = empty_game(config);
+        // a short cliff right below the jumper (which rises past it in a single tick)
+        let (x, y) = game.jumper.position();
+        let mut cliff = Cliff::block(x - 2, (game.jumper.size().0 + 4, 4), &game.jumper);
+        cliff.y_pos = y + game.jumper.size().1;
+        game.cliffs.push(cliff);
+        game.num_cliffs = 1;
+
+        let state = game.step(Input::Tick);
+        assert!(game.cliffs[0].y_pos + 4 <= y);     // (it's above the jumper now)
+        assert!(state.is_over());
+
+        // (and it's only the rows that it's passed through which count)
+        let mut game = empty_game(config);
+        let mut cliff = Cliff::block(x - 2, (game.jumper.size().0 + 4, 4), &game.jumper);
+        cliff.y_pos = y + game.jumper.size().1 + config.cliff_y;
+        game.cliffs.push(cliff);
+        game.num_cliffs = 1;
+        assert!(!game.step(Input::Tick).is_over());
     }
 }